@@ -0,0 +1,31 @@
+// Smoke test of the generated Kotlin bindings, recovering Alice secret.
+//
+// Generate the bindings as described in `src/mobile.rs` docs, compile
+// them, and run the script with JNA and the library available:
+//
+// kotlinc out/uniffi/banana_recovery/banana_recovery.kt -cp jna.jar -d bindings.jar
+// JAVA_OPTS=-Djna.library.path=target/release \
+//     kotlinc -cp jna.jar:bindings.jar -script examples/kotlin/smoke.kts
+
+import uniffi.banana_recovery.*
+
+fun String.hexToBytes(): ByteArray = chunked(2).map { it.toInt(16).toByte() }.toByteArray()
+
+val scanA1 = "7b2276223a312c2274223a22416c6963652074726965732042616e616e6153706c697420616761696e222c2272223a322c2264223a223841666c74524d465a42425930326b3675457262364e747a375855364957796747764649444c4247566167542f6e7a5365507a55304e7a436e7175795975363765666634675462674564445542787671594f4d32557048326c6758544c673667583437546c694958554d66317562322f7675726c7479727769516b564e5636505158673d3d222c226e223a226f39446270426939723755574a484f726975444172523456726330564f6f336c227d"
+val scanA2 = "7b2276223a312c2274223a22416c6963652074726965732042616e616e6153706c697420616761696e222c2272223a322c2264223a223841752f61694a2b794343786f715a7843434d6e32312f426358675a4b4935316b55742b644a6d6f782f7255456c3434485149547a437055414a38516835635a302b7155717067554d76697161777238763671786d3959544f4e636e66667942774249693067634b576f776463776f31664270456b5176357757694358654f38486a773d3d222c226e223a226f39446270426939723755574a484f726975444172523456726330564f6f336c227d"
+
+val collection = MobileShareCollection()
+collection.addShare(MobileShare(scanA1.hexToBytes()))
+try {
+    collection.addShare(MobileShare(scanA1.hexToBytes()))
+    throw AssertionError("same share must not be added twice")
+} catch (e: MobileException.IncompatibleShare) {
+    check(e.code.toInt() == 202)
+}
+check(collection.sharesRequired() == 2u)
+check(!collection.isReady())
+
+collection.addShare(MobileShare(scanA2.hexToBytes()))
+val secret = collection.combined()!!.recoverWithPassphrase("blighted-comprised-bucktooth-disjoin")
+check(secret == "bottom drive obey lake curtain smoke basket hold race lonely fit walk")
+println("ok")
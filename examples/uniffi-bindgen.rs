@@ -0,0 +1,4 @@
+//! UniFFI bindings generator, for the library built with `uniffi` feature.
+fn main() {
+    uniffi::uniffi_bindgen_main()
+}
@@ -0,0 +1,9 @@
+#![no_main]
+
+use banana_recovery::{FuzzPayload, Share};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|payload: FuzzPayload| {
+    let _ = Share::new(payload.0.clone());
+    let _ = Share::new_strict(payload.0);
+});
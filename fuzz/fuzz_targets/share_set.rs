@@ -0,0 +1,24 @@
+#![no_main]
+
+use banana_recovery::{FuzzSet, Share, ShareCollection};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|set: FuzzSet| {
+    let mut share_collection = ShareCollection::new();
+    for payload in set.payloads {
+        if let Ok(share) = Share::new(payload) {
+            let _ = share_collection.add_share_lenient(share);
+        }
+    }
+    match &share_collection {
+        ShareCollection::Empty => {}
+        ShareCollection::InProgress(in_progress) => {
+            let _ = in_progress.consistency_check();
+            let _ = in_progress.derive_new_share(set.new_id);
+        }
+        ShareCollection::Ready(combined) => {
+            let _ = combined.consistency_check();
+            let _ = combined.derive_new_share(set.new_id);
+        }
+    }
+});
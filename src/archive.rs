@@ -0,0 +1,49 @@
+//! Share archives import.
+use std::{io::BufRead, vec::Vec};
+
+use crate::error::BananaError;
+use crate::shares::Share;
+
+/// Parse newline-delimited archive of share QR payloads.
+///
+/// Each archive line is expected to contain a single QR payload, i.e. the
+/// share json. Trailing `\r` is removed, blank lines are skipped.
+///
+/// `on_progress` is called with the line number (starting from `1`) after
+/// each line is processed, including the blank ones.
+///
+/// Parsing results are returned together with corresponding line numbers,
+/// for error reporting. Failure to read the archive results in
+/// [`BananaError::ArchiveReadFailed`] for the unread line, and the parsing
+/// stops.
+pub fn parse_archive(
+    mut reader: impl BufRead,
+    mut on_progress: impl FnMut(usize),
+) -> Vec<(usize, Result<Share, BananaError>)> {
+    let mut results = Vec::new();
+    let mut line_number = 0;
+    loop {
+        line_number += 1;
+        let mut line = Vec::new();
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) => break,
+            Ok(_) => {
+                if line.last() == Some(&b'\n') {
+                    let _ = line.pop();
+                }
+                if line.last() == Some(&b'\r') {
+                    let _ = line.pop();
+                }
+                if !line.iter().all(u8::is_ascii_whitespace) {
+                    results.push((line_number, Share::new(line)));
+                }
+                on_progress(line_number);
+            }
+            Err(_) => {
+                results.push((line_number, Err(BananaError::ArchiveReadFailed)));
+                break;
+            }
+        }
+    }
+    results
+}
@@ -0,0 +1,105 @@
+//! Decompression of secrets compressed before encryption.
+use alloc::vec::Vec;
+
+use miniz_oxide::inflate::{decompress_to_vec_with_limit, decompress_to_vec_zlib_with_limit};
+use zeroize::Zeroize;
+
+use crate::error::BananaError;
+
+/// Maximum size of decompressed secret.
+///
+/// Secrets fit into the QR codes, so this is generous, and only guards
+/// against decompression bombs.
+const DECOMPRESSED_LIMIT: usize = 1 << 20;
+
+/// gzip header flags.
+const FHCRC: u8 = 0x02;
+const FEXTRA: u8 = 0x04;
+const FNAME: u8 = 0x08;
+const FCOMMENT: u8 = 0x10;
+
+/// Decompress the data, with format detected by the magic header.
+///
+/// Supported formats are gzip (with the trailer CRC32 and size checked) and
+/// zlib (with Adler-32 checked by the decompressor). Data without either
+/// header is rejected.
+pub(crate) fn decompress(data: &[u8]) -> Result<Vec<u8>, BananaError> {
+    let decompressed = match data {
+        [0x1f, 0x8b, ..] => gunzip(data)?,
+        [cmf, flg, ..] if cmf & 0x0f == 8 && (u16::from_be_bytes([*cmf, *flg]) % 31 == 0) => {
+            decompress_to_vec_zlib_with_limit(data, DECOMPRESSED_LIMIT).map_err(|mut e| {
+                e.output.zeroize();
+                BananaError::DecompressionFailed
+            })?
+        }
+        _ => return Err(BananaError::DecompressionFailed),
+    };
+    Ok(decompressed)
+}
+
+/// Decompress gzip data.
+fn gunzip(data: &[u8]) -> Result<Vec<u8>, BananaError> {
+    // fixed header is 10 bytes, followed by optional fields;
+    // trailer is 8 bytes
+    if data.len() < 18 || data[2] != 8 {
+        return Err(BananaError::DecompressionFailed);
+    }
+    let flags = data[3];
+    let mut position = 10;
+    if flags & FEXTRA != 0 {
+        let extra_length = match data.get(position..position + 2) {
+            Some(a) => u16::from_le_bytes([a[0], a[1]]) as usize,
+            None => return Err(BananaError::DecompressionFailed),
+        };
+        position += 2 + extra_length;
+    }
+    for flag in [FNAME, FCOMMENT] {
+        if flags & flag != 0 {
+            // zero-terminated string
+            match data
+                .get(position..)
+                .and_then(|a| a.iter().position(|x| *x == 0))
+            {
+                Some(a) => position += a + 1,
+                None => return Err(BananaError::DecompressionFailed),
+            }
+        }
+    }
+    if flags & FHCRC != 0 {
+        position += 2;
+    }
+    let body = match data.get(position..data.len() - 8) {
+        Some(a) => a,
+        None => return Err(BananaError::DecompressionFailed),
+    };
+    let trailer = &data[data.len() - 8..];
+    let crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+    let size = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+
+    let mut decompressed =
+        decompress_to_vec_with_limit(body, DECOMPRESSED_LIMIT).map_err(|mut e| {
+            e.output.zeroize();
+            BananaError::DecompressionFailed
+        })?;
+    if crc32(&decompressed) != crc || decompressed.len() as u32 != size {
+        decompressed.zeroize();
+        return Err(BananaError::DecompressionFailed);
+    }
+    Ok(decompressed)
+}
+
+/// CRC-32 (ISO-HDLC) as used in gzip, bitwise.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data.iter() {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
@@ -0,0 +1,54 @@
+//! Deduplication of rapidly repeated scans.
+use sha2::{Digest, Sha256};
+
+/// Memory of recently seen QR payloads.
+///
+/// Live scanners produce the same QR payload many times per second. This
+/// deduplicator keeps SHA-256 hashes of last `N` payloads in a fixed-size
+/// ring, so that the repeated payloads could be skipped cheaply before any
+/// [`Share`](crate::Share) construction.
+///
+/// No heap allocations are made, the memory footprint is fixed by `N`.
+#[derive(Debug)]
+pub struct ScanDeduplicator<const N: usize> {
+    hashes: [Option<[u8; 32]>; N],
+    next: usize,
+}
+
+impl<const N: usize> ScanDeduplicator<N> {
+    /// New empty deduplicator.
+    pub const fn new() -> Self {
+        Self {
+            hashes: [None; N],
+            next: 0,
+        }
+    }
+
+    /// Check if the payload is not among `N` last new payloads, and remember
+    /// it if so.
+    ///
+    /// With `N = 0` every payload is new.
+    pub fn is_new(&mut self, payload: &[u8]) -> bool {
+        let hash: [u8; 32] = Sha256::digest(payload).into();
+        if self.hashes.contains(&Some(hash)) {
+            false
+        } else {
+            if N != 0 {
+                self.hashes[self.next] = Some(hash);
+                self.next = (self.next + 1) % N;
+            }
+            true
+        }
+    }
+
+    /// Forget all remembered payloads.
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+}
+
+impl<const N: usize> Default for ScanDeduplicator<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
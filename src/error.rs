@@ -1,9 +1,11 @@
-//! Errors.
-use crate::shares::BIT_RANGE;
+//! Errors and warnings.
+use crate::shares::{Version, BIT_RANGE, NONCE_LENGTH, TAG_LENGTH};
+
+use core::error::Error;
 
 #[cfg(feature = "std")]
 use std::{
-    error::Error,
+    boxed::Box,
     fmt::{Display, Formatter, Result as FmtResult},
     string::String,
 };
@@ -12,58 +14,400 @@ use std::{
 use core::fmt::{Display, Formatter, Result as FmtResult};
 
 #[cfg(not(feature = "std"))]
-use alloc::string::String;
+use alloc::{boxed::Box, string::String};
 
 /// Errors in split data recovery.
+///
+/// Errors are compared with all the carried data.
+///
+/// `BananaError` implements `core::error::Error` (Rust 1.81 or newer), also
+/// without `std` feature:
+///
+///```
+/// #![no_std]
+/// # extern crate std;
+/// use banana_recovery::BananaError;
+/// use core::error::Error;
+///
+/// fn reason(error: &dyn Error) -> Option<&(dyn Error + 'static)> {
+///     error.source()
+/// }
+///
+/// # fn main() {
+/// assert!(reason(&BananaError::DecodingFailed).is_none());
+/// # }
+///```
 #[allow(missing_docs)]
-#[derive(Debug)]
+#[derive(Debug, Eq, PartialEq)]
 #[non_exhaustive]
 pub enum BananaError {
+    ArchiveEncryptionFailed,
+    ArchiveReadFailed,
+    Argon2Failed,
     BitsOutOfRange(u32),
+    CombinedDataMalformed(usize),
     DecodedSecretNotString,
     DecodingFailed,
+    DecompressionFailed,
+    DerivedShareUnrepresentable(u32),
     EmptyShare,
-    JsonParsing,
+    FieldInvariantViolated {
+        context: &'static str,
+    },
+    IncompatibleCollections {
+        reason: Box<BananaError>,
+    },
+    InsufficientShares {
+        have: usize,
+        need: usize,
+    },
+    JsonParsing(String),
     LogOutOfRange(u32),
+    MalformedPadding,
+    NoShares,
     NonceNotBase64,
-    NotShareString,
+    NonceWrongLength(usize),
+    NotShareString(usize),
     ParseBit(char),
+    PayloadNotHex,
+    PolynomialMissing(u32),
+    PolynomialNotPrimitive(u32),
+    RequiredSharesExceedsField {
+        required: usize,
+        max: u32,
+    },
+    RequiredSharesTooSmall(usize),
     ScryptFailed,
+    SecretHashMismatch,
     ShareAlreadyInSet,
     ShareBitsDifferent,
+    ShareContentDuplicate {
+        existing_id: u32,
+        new_id: u32,
+    },
     ShareContentLengthDifferent,
+    ShareIdInvalid(u32),
+    ShareIdNotInSet(u32),
     ShareNonceDifferent,
+    ShareRejected {
+        index: usize,
+        reason: Box<BananaError>,
+    },
     ShareRequiredSharesDifferent,
-    ShareTitleDifferent { set: String, new_share: String },
+    ShareTitleDifferent {
+        set: String,
+        new_share: String,
+    },
+    ShareTooLarge {
+        len: usize,
+        max: usize,
+    },
     ShareTooShort,
     ShareVersionDifferent,
+    SplitParametersInvalid {
+        total_shares: usize,
+        required_shares: usize,
+    },
+    ThresholdUnreachable {
+        have: usize,
+        need: usize,
+    },
     UndefinedBodyNotHex,
+    UriMalformed {
+        reason: &'static str,
+    },
+    UriSchemeUnsupported,
+    VectorMismatch(usize),
+    VerificationMismatch,
     VersionNotSupported(u8),
     BodyNotBase64,
 }
 
+/// Broad category of [`BananaError`], for programmatic handling.
+///
+/// Unlike [`BananaError`], this enum is not expected to grow.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ErrorKind {
+    /// Share could not be parsed, likely a bad scan. Rescanning the code
+    /// could help.
+    ///
+    /// `BitsOutOfRange`, `BodyNotBase64`, `EmptyShare`, `JsonParsing`,
+    /// `NonceNotBase64`, `NonceWrongLength`, `NotShareString`, `ParseBit`,
+    /// `PayloadNotHex`, `RequiredSharesExceedsField`, `RequiredSharesTooSmall`,
+    /// `ShareIdInvalid`, `ShareTooLarge`, `ShareTooShort`,
+    /// `UndefinedBodyNotHex`, `UriMalformed`, `UriSchemeUnsupported`,
+    /// `VersionNotSupported`.
+    InvalidShare,
+
+    /// Share is valid, but does not fit with the other shares, likely from a
+    /// different set, or the requested share is not in the set.
+    ///
+    /// `IncompatibleCollections`, `ShareAlreadyInSet`, `ShareBitsDifferent`,
+    /// `ShareContentLengthDifferent`, `ShareIdNotInSet`, `ShareNonceDifferent`,
+    /// `ShareRequiredSharesDifferent`, `ShareTitleDifferent`,
+    /// `ShareVersionDifferent`.
+    IncompatibleShare,
+
+    /// Shares fit together, but do not yield the secret, likely some shares
+    /// are damaged or missing.
+    ///
+    /// `CombinedDataMalformed`, `DecodedSecretNotString`,
+    /// `DecompressionFailed`, `InsufficientShares`, `LogOutOfRange`,
+    /// `MalformedPadding`, `NoShares`, `SecretHashMismatch`,
+    /// `ShareContentDuplicate`, `ThresholdUnreachable`, `VerificationMismatch`.
+    CorruptedSet,
+
+    /// Secret could not be decrypted, likely the passphrase is wrong.
+    ///
+    /// `DecodingFailed`.
+    DecryptionFailed,
+
+    /// Calculation failed, or the request itself is invalid.
+    ///
+    /// `ArchiveEncryptionFailed`, `ArchiveReadFailed`, `Argon2Failed`,
+    /// `DerivedShareUnrepresentable`, `FieldInvariantViolated`,
+    /// `PolynomialMissing`, `PolynomialNotPrimitive`, `ScryptFailed`,
+    /// `SplitParametersInvalid`, `VectorMismatch`.
+    Internal,
+}
+
+/// Numeric codes of [`BananaError`] variants, with variant names, as
+/// returned by [`BananaError::code`].
+///
+/// Codes are stable: new variants get new codes, and codes are never changed
+/// or reused. Hundreds digit is the [`ErrorKind`]: `1` for
+/// [`ErrorKind::InvalidShare`], `2` for [`ErrorKind::IncompatibleShare`], `3`
+/// for [`ErrorKind::CorruptedSet`], `4` for [`ErrorKind::DecryptionFailed`],
+/// and `5` for [`ErrorKind::Internal`].
+///
+/// [`BananaError::ShareRejected`] is not listed, its code is
+/// [`SHARE_REJECTED_CODE_OFFSET`] plus the code of the rejection reason.
+pub const ERROR_CODES: &[(u16, &str)] = &[
+    (101, "BitsOutOfRange"),
+    (102, "BodyNotBase64"),
+    (103, "EmptyShare"),
+    (104, "JsonParsing"),
+    (105, "NonceNotBase64"),
+    (106, "NonceWrongLength"),
+    (107, "NotShareString"),
+    (108, "ParseBit"),
+    (109, "PayloadNotHex"),
+    (110, "RequiredSharesExceedsField"),
+    (111, "RequiredSharesTooSmall"),
+    (112, "ShareIdInvalid"),
+    (113, "ShareTooLarge"),
+    (114, "ShareTooShort"),
+    (115, "UndefinedBodyNotHex"),
+    (116, "VersionNotSupported"),
+    (117, "UriMalformed"),
+    (118, "UriSchemeUnsupported"),
+    (201, "IncompatibleCollections"),
+    (202, "ShareAlreadyInSet"),
+    (203, "ShareBitsDifferent"),
+    (204, "ShareContentLengthDifferent"),
+    (205, "ShareIdNotInSet"),
+    (206, "ShareNonceDifferent"),
+    (207, "ShareRequiredSharesDifferent"),
+    (208, "ShareTitleDifferent"),
+    (209, "ShareVersionDifferent"),
+    (301, "CombinedDataMalformed"),
+    (302, "DecodedSecretNotString"),
+    (303, "DecompressionFailed"),
+    (304, "LogOutOfRange"),
+    (305, "MalformedPadding"),
+    (306, "SecretHashMismatch"),
+    (307, "ShareContentDuplicate"),
+    (308, "ThresholdUnreachable"),
+    (309, "VerificationMismatch"),
+    (310, "InsufficientShares"),
+    (311, "NoShares"),
+    (401, "DecodingFailed"),
+    (501, "ArchiveReadFailed"),
+    (502, "Argon2Failed"),
+    (503, "FieldInvariantViolated"),
+    (504, "PolynomialMissing"),
+    (505, "PolynomialNotPrimitive"),
+    (506, "ScryptFailed"),
+    (507, "SplitParametersInvalid"),
+    (508, "VectorMismatch"),
+    (509, "DerivedShareUnrepresentable"),
+    (510, "ArchiveEncryptionFailed"),
+];
+
+/// Offset added to the code of the rejection reason in
+/// [`BananaError::ShareRejected`] code.
+pub const SHARE_REJECTED_CODE_OFFSET: u16 = 1000;
+
 impl BananaError {
+    /// Broad category of the error.
+    ///
+    /// [`BananaError::ShareRejected`] has the kind of the rejection reason.
+    pub fn kind(&self) -> ErrorKind {
+        match &self {
+            BananaError::BitsOutOfRange(_)
+            | BananaError::BodyNotBase64
+            | BananaError::EmptyShare
+            | BananaError::JsonParsing(_)
+            | BananaError::NonceNotBase64
+            | BananaError::NonceWrongLength(_)
+            | BananaError::NotShareString(_)
+            | BananaError::ParseBit(_)
+            | BananaError::PayloadNotHex
+            | BananaError::RequiredSharesExceedsField { .. }
+            | BananaError::RequiredSharesTooSmall(_)
+            | BananaError::ShareIdInvalid(_)
+            | BananaError::ShareTooLarge { .. }
+            | BananaError::ShareTooShort
+            | BananaError::UndefinedBodyNotHex
+            | BananaError::UriMalformed { .. }
+            | BananaError::UriSchemeUnsupported
+            | BananaError::VersionNotSupported(_) => ErrorKind::InvalidShare,
+            BananaError::IncompatibleCollections { .. }
+            | BananaError::ShareAlreadyInSet
+            | BananaError::ShareBitsDifferent
+            | BananaError::ShareContentLengthDifferent
+            | BananaError::ShareIdNotInSet(_)
+            | BananaError::ShareNonceDifferent
+            | BananaError::ShareRequiredSharesDifferent
+            | BananaError::ShareTitleDifferent { .. }
+            | BananaError::ShareVersionDifferent => ErrorKind::IncompatibleShare,
+            BananaError::CombinedDataMalformed(_)
+            | BananaError::DecodedSecretNotString
+            | BananaError::DecompressionFailed
+            | BananaError::InsufficientShares { .. }
+            | BananaError::LogOutOfRange(_)
+            | BananaError::MalformedPadding
+            | BananaError::NoShares
+            | BananaError::SecretHashMismatch
+            | BananaError::ShareContentDuplicate { .. }
+            | BananaError::ThresholdUnreachable { .. }
+            | BananaError::VerificationMismatch => ErrorKind::CorruptedSet,
+            BananaError::DecodingFailed => ErrorKind::DecryptionFailed,
+            BananaError::ArchiveEncryptionFailed
+            | BananaError::ArchiveReadFailed
+            | BananaError::Argon2Failed
+            | BananaError::DerivedShareUnrepresentable(_)
+            | BananaError::FieldInvariantViolated { .. }
+            | BananaError::PolynomialMissing(_)
+            | BananaError::PolynomialNotPrimitive(_)
+            | BananaError::ScryptFailed
+            | BananaError::SplitParametersInvalid { .. }
+            | BananaError::VectorMismatch(_) => ErrorKind::Internal,
+            BananaError::ShareRejected { reason, .. } => reason.kind(),
+        }
+    }
+
+    /// Stable numeric code of the error, as listed in [`ERROR_CODES`].
+    ///
+    /// [`BananaError::ShareRejected`] has code
+    /// [`SHARE_REJECTED_CODE_OFFSET`] plus the code of the rejection reason.
+    pub fn code(&self) -> u16 {
+        match &self {
+            BananaError::BitsOutOfRange(_) => 101,
+            BananaError::BodyNotBase64 => 102,
+            BananaError::EmptyShare => 103,
+            BananaError::JsonParsing(_) => 104,
+            BananaError::NonceNotBase64 => 105,
+            BananaError::NonceWrongLength(_) => 106,
+            BananaError::NotShareString(_) => 107,
+            BananaError::ParseBit(_) => 108,
+            BananaError::PayloadNotHex => 109,
+            BananaError::RequiredSharesExceedsField { .. } => 110,
+            BananaError::RequiredSharesTooSmall(_) => 111,
+            BananaError::ShareIdInvalid(_) => 112,
+            BananaError::ShareTooLarge { .. } => 113,
+            BananaError::ShareTooShort => 114,
+            BananaError::UndefinedBodyNotHex => 115,
+            BananaError::VersionNotSupported(_) => 116,
+            BananaError::UriMalformed { .. } => 117,
+            BananaError::UriSchemeUnsupported => 118,
+            BananaError::IncompatibleCollections { .. } => 201,
+            BananaError::ShareAlreadyInSet => 202,
+            BananaError::ShareBitsDifferent => 203,
+            BananaError::ShareContentLengthDifferent => 204,
+            BananaError::ShareIdNotInSet(_) => 205,
+            BananaError::ShareNonceDifferent => 206,
+            BananaError::ShareRequiredSharesDifferent => 207,
+            BananaError::ShareTitleDifferent { .. } => 208,
+            BananaError::ShareVersionDifferent => 209,
+            BananaError::CombinedDataMalformed(_) => 301,
+            BananaError::DecodedSecretNotString => 302,
+            BananaError::DecompressionFailed => 303,
+            BananaError::LogOutOfRange(_) => 304,
+            BananaError::MalformedPadding => 305,
+            BananaError::SecretHashMismatch => 306,
+            BananaError::ShareContentDuplicate { .. } => 307,
+            BananaError::ThresholdUnreachable { .. } => 308,
+            BananaError::VerificationMismatch => 309,
+            BananaError::InsufficientShares { .. } => 310,
+            BananaError::NoShares => 311,
+            BananaError::DecodingFailed => 401,
+            BananaError::ArchiveReadFailed => 501,
+            BananaError::Argon2Failed => 502,
+            BananaError::FieldInvariantViolated { .. } => 503,
+            BananaError::PolynomialMissing(_) => 504,
+            BananaError::PolynomialNotPrimitive(_) => 505,
+            BananaError::ScryptFailed => 506,
+            BananaError::SplitParametersInvalid { .. } => 507,
+            BananaError::VectorMismatch(_) => 508,
+            BananaError::DerivedShareUnrepresentable(_) => 509,
+            BananaError::ArchiveEncryptionFailed => 510,
+            BananaError::ShareRejected { reason, .. } => {
+                SHARE_REJECTED_CODE_OFFSET.saturating_add(reason.code())
+            }
+        }
+    }
+
     fn error_text(&self) -> String {
         match &self {
+            BananaError::ArchiveEncryptionFailed => String::from("Unable to encrypt the share archive."),
+            BananaError::ArchiveReadFailed => String::from("Unable to read the share archive."),
+            BananaError::Argon2Failed => String::from("Argon2 calculation failed."),
             BananaError::BitsOutOfRange(bits) => format!("Bits in share data {} are outside of expected range [{:?}]. Likely the share is damaged.", bits, BIT_RANGE),
+            BananaError::CombinedDataMalformed(len) => format!("Combined data is {} bytes long, shorter than {} bytes authentication tag. Likely some shares are damaged.", len, TAG_LENGTH),
             BananaError::DecodedSecretNotString => String::from("Decoded secret could not be displayed as a string."),
             BananaError::DecodingFailed => String::from("Unable to decode the secret."),
+            BananaError::DecompressionFailed => String::from("Unable to decompress the decoded secret."),
+            BananaError::DerivedShareUnrepresentable(id) => format!("Content of share with id {} could not be represented in bytes for the share bits value.", id),
             BananaError::EmptyShare => String::from("Share contains no data."),
-            BananaError::JsonParsing => String::from("Unable to parse the input as a json object."),
+            BananaError::FieldInvariantViolated { context } => format!("Galois field calculation could not proceed: {}.", context),
+            BananaError::IncompatibleCollections { reason } => format!("Share collections do not belong to the same set. {}", reason),
+            BananaError::InsufficientShares { have, need } => format!("Not enough shares to recover the secret. Got {} shares, {} are needed.", have, need),
+            BananaError::JsonParsing(reason) => format!("Unable to parse the input as a json object: {}.", reason),
             BananaError::LogOutOfRange(log) => format!("While processing, tried addressing log[{}] out of expected range. Likely the share is damaged.", log),
+            BananaError::MalformedPadding => String::from("Combined shares data has no valid padding marker. Likely the shares are damaged."),
+            BananaError::NoShares => String::from("No shares provided."),
             BananaError::NonceNotBase64 => String::from("Nonce is not in base64 format."),
-            BananaError::NotShareString => String::from("Received QR code could not be read as a string."),
+            BananaError::NonceWrongLength(length) => format!("Nonce length {} bytes is different from expected {} bytes.", length, NONCE_LENGTH),
+            BananaError::NotShareString(valid_up_to) => format!("Received QR code could not be read as a string. Invalid UTF-8 sequence at byte {}.", valid_up_to),
             BananaError::ParseBit(ch) => format!("Unable to parse first data char '{}' as a number in radix36 format.", ch),
+            BananaError::PayloadNotHex => String::from("QR data is not in hex format."),
+            BananaError::PolynomialMissing(bits) => format!("No primitive polynomial provided for bits value {}.", bits),
+            BananaError::PolynomialNotPrimitive(polynomial) => format!("Polynomial {} is not primitive for the set bits value.", polynomial),
+            BananaError::RequiredSharesExceedsField { required, max } => format!("Share requires {} shares, but no more than {} shares could exist for share bits value.", required, max),
+            BananaError::RequiredSharesTooSmall(required) => format!("Share requires {} shares, at least 2 are needed for a split secret.", required),
             BananaError::ScryptFailed => String::from("Scrypt calculation failed."),
+            BananaError::SecretHashMismatch => String::from("Recovered secret does not match the expected hash."),
             BananaError::ShareAlreadyInSet => String::from("Share is already in the set."),
             BananaError::ShareBitsDifferent => String::from("Share could not be added to the set. Bits setting is different."),
+            BananaError::ShareContentDuplicate { existing_id, new_id } => format!("Share with id {} has content identical to the share with id {} already in the set. Likely the shares are damaged.", new_id, existing_id),
             BananaError::ShareContentLengthDifferent => String::from("Share could not be added to the set. Content length is different."),
+            BananaError::ShareIdInvalid(id) => format!("Share id {} is not valid for the share bits value.", id),
+            BananaError::ShareIdNotInSet(id) => format!("Share with id {} is not in the set.", id),
             BananaError::ShareNonceDifferent => String::from("Share could not be added to the set. Nonce is different."),
+            BananaError::ShareRejected { index, reason } => format!("Share at position {} was rejected. {}", index, reason),
             BananaError::ShareRequiredSharesDifferent => String::from("Share could not be added to the set. Number of required shares is different."),
             BananaError::ShareTitleDifferent { set, new_share } => format!("Share could not be added to the set. Title in set {} does not match the title of the share {}.", set, new_share),
+            BananaError::ShareTooLarge { len, max } => format!("Share content is {} bytes long, more than allowed {} bytes.", len, max),
             BananaError::ShareTooShort => String::from("Share content is too short to separate share id properly. Likely the share is damaged."),
             BananaError::ShareVersionDifferent => String::from("Share could not be added to the set. The version is different."),
+            BananaError::SplitParametersInvalid { total_shares, required_shares } => format!("Unable to split the secret into {} shares with {} required.", total_shares, required_shares),
+            BananaError::ThresholdUnreachable { have, need } => format!("Secret could not be recovered with available shares. Collected {} shares, at least {} are needed.", have, need),
             BananaError::UndefinedBodyNotHex => String::from("Share with undefined version was expected to have hexadecimal content."),
+            BananaError::UriMalformed { reason } => format!("Unable to extract share from the uri: {}.", reason),
+            BananaError::UriSchemeUnsupported => String::from("Uri is neither a banana share link nor a json data uri."),
+            BananaError::VectorMismatch(index) => format!("Secret recovered for test vector {} does not match the expected one.", index),
+            BananaError::VerificationMismatch => String::from("Re-encrypted secret does not match the combined shares data."),
             BananaError::VersionNotSupported(version) => format!("Version {} is not supported.", version),
             BananaError::BodyNotBase64 => String::from("Share with version V1 was expected to have content in base64 format."),
         }
@@ -76,9 +420,183 @@ impl Display for BananaError {
     }
 }
 
-#[cfg(feature = "std")]
+/// Compact [`defmt`] encoding, without the text formatting of [`Display`].
+#[cfg(feature = "defmt")]
+impl defmt::Format for BananaError {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match &self {
+            BananaError::ArchiveEncryptionFailed => defmt::write!(f, "ArchiveEncryptionFailed"),
+            BananaError::ArchiveReadFailed => defmt::write!(f, "ArchiveReadFailed"),
+            BananaError::Argon2Failed => defmt::write!(f, "Argon2Failed"),
+            BananaError::BitsOutOfRange(bits) => defmt::write!(f, "BitsOutOfRange({=u32})", bits),
+            BananaError::CombinedDataMalformed(len) => {
+                defmt::write!(f, "CombinedDataMalformed({=usize})", len)
+            }
+            BananaError::DecodedSecretNotString => defmt::write!(f, "DecodedSecretNotString"),
+            BananaError::DecodingFailed => defmt::write!(f, "DecodingFailed"),
+            BananaError::DecompressionFailed => defmt::write!(f, "DecompressionFailed"),
+            BananaError::DerivedShareUnrepresentable(id) => {
+                defmt::write!(f, "DerivedShareUnrepresentable({=u32})", id)
+            }
+            BananaError::EmptyShare => defmt::write!(f, "EmptyShare"),
+            BananaError::FieldInvariantViolated { context } => {
+                defmt::write!(f, "FieldInvariantViolated {{ context: {=str} }}", context)
+            }
+            BananaError::IncompatibleCollections { reason } => {
+                defmt::write!(f, "IncompatibleCollections {{ reason: {} }}", reason)
+            }
+            BananaError::InsufficientShares { have, need } => defmt::write!(
+                f,
+                "InsufficientShares {{ have: {=usize}, need: {=usize} }}",
+                have,
+                need
+            ),
+            BananaError::JsonParsing(reason) => {
+                defmt::write!(f, "JsonParsing({=str})", reason.as_str())
+            }
+            BananaError::LogOutOfRange(log) => defmt::write!(f, "LogOutOfRange({=u32})", log),
+            BananaError::MalformedPadding => defmt::write!(f, "MalformedPadding"),
+            BananaError::NoShares => defmt::write!(f, "NoShares"),
+            BananaError::NonceNotBase64 => defmt::write!(f, "NonceNotBase64"),
+            BananaError::NonceWrongLength(length) => {
+                defmt::write!(f, "NonceWrongLength({=usize})", length)
+            }
+            BananaError::NotShareString(valid_up_to) => {
+                defmt::write!(f, "NotShareString({=usize})", valid_up_to)
+            }
+            BananaError::ParseBit(ch) => defmt::write!(f, "ParseBit({=char})", ch),
+            BananaError::PayloadNotHex => defmt::write!(f, "PayloadNotHex"),
+            BananaError::PolynomialMissing(bits) => {
+                defmt::write!(f, "PolynomialMissing({=u32})", bits)
+            }
+            BananaError::PolynomialNotPrimitive(polynomial) => {
+                defmt::write!(f, "PolynomialNotPrimitive({=u32})", polynomial)
+            }
+            BananaError::RequiredSharesExceedsField { required, max } => defmt::write!(
+                f,
+                "RequiredSharesExceedsField {{ required: {=usize}, max: {=u32} }}",
+                required,
+                max
+            ),
+            BananaError::RequiredSharesTooSmall(required) => {
+                defmt::write!(f, "RequiredSharesTooSmall({=usize})", required)
+            }
+            BananaError::ScryptFailed => defmt::write!(f, "ScryptFailed"),
+            BananaError::SecretHashMismatch => defmt::write!(f, "SecretHashMismatch"),
+            BananaError::ShareAlreadyInSet => defmt::write!(f, "ShareAlreadyInSet"),
+            BananaError::ShareBitsDifferent => defmt::write!(f, "ShareBitsDifferent"),
+            BananaError::ShareContentDuplicate {
+                existing_id,
+                new_id,
+            } => defmt::write!(
+                f,
+                "ShareContentDuplicate {{ existing_id: {=u32}, new_id: {=u32} }}",
+                existing_id,
+                new_id
+            ),
+            BananaError::ShareContentLengthDifferent => {
+                defmt::write!(f, "ShareContentLengthDifferent")
+            }
+            BananaError::ShareIdInvalid(id) => defmt::write!(f, "ShareIdInvalid({=u32})", id),
+            BananaError::ShareIdNotInSet(id) => defmt::write!(f, "ShareIdNotInSet({=u32})", id),
+            BananaError::ShareNonceDifferent => defmt::write!(f, "ShareNonceDifferent"),
+            BananaError::ShareRejected { index, reason } => defmt::write!(
+                f,
+                "ShareRejected {{ index: {=usize}, reason: {} }}",
+                index,
+                reason
+            ),
+            BananaError::ShareRequiredSharesDifferent => {
+                defmt::write!(f, "ShareRequiredSharesDifferent")
+            }
+            BananaError::ShareTitleDifferent { set, new_share } => defmt::write!(
+                f,
+                "ShareTitleDifferent {{ set: {=str}, new_share: {=str} }}",
+                set.as_str(),
+                new_share.as_str()
+            ),
+            BananaError::ShareTooLarge { len, max } => defmt::write!(
+                f,
+                "ShareTooLarge {{ len: {=usize}, max: {=usize} }}",
+                len,
+                max
+            ),
+            BananaError::ShareTooShort => defmt::write!(f, "ShareTooShort"),
+            BananaError::ShareVersionDifferent => defmt::write!(f, "ShareVersionDifferent"),
+            BananaError::SplitParametersInvalid {
+                total_shares,
+                required_shares,
+            } => defmt::write!(
+                f,
+                "SplitParametersInvalid {{ total_shares: {=usize}, required_shares: {=usize} }}",
+                total_shares,
+                required_shares
+            ),
+            BananaError::ThresholdUnreachable { have, need } => defmt::write!(
+                f,
+                "ThresholdUnreachable {{ have: {=usize}, need: {=usize} }}",
+                have,
+                need
+            ),
+            BananaError::UndefinedBodyNotHex => defmt::write!(f, "UndefinedBodyNotHex"),
+            BananaError::UriMalformed { reason } => {
+                defmt::write!(f, "UriMalformed {{ reason: {=str} }}", reason)
+            }
+            BananaError::UriSchemeUnsupported => defmt::write!(f, "UriSchemeUnsupported"),
+            BananaError::VectorMismatch(index) => {
+                defmt::write!(f, "VectorMismatch({=usize})", index)
+            }
+            BananaError::VerificationMismatch => defmt::write!(f, "VerificationMismatch"),
+            BananaError::VersionNotSupported(version) => {
+                defmt::write!(f, "VersionNotSupported({=u8})", version)
+            }
+            BananaError::BodyNotBase64 => defmt::write!(f, "BodyNotBase64"),
+        }
+    }
+}
+
+/// Non-fatal irregularities found in otherwise valid data.
+#[derive(Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum BananaWarning {
+    /// Bits value is within allowed range, but differs from the one known to
+    /// be used with this protocol version. Likely the bits prefix is damaged.
+    UnusualBitsForVersion {
+        /// Protocol version.
+        version: Version,
+
+        /// Bits value found in share.
+        bits: u32,
+    },
+}
+
+impl BananaWarning {
+    fn warning_text(&self) -> String {
+        match &self {
+            BananaWarning::UnusualBitsForVersion { version, bits } => format!(
+                "Bits value {} is unusual for share version {}. Likely the share is damaged.",
+                bits, version
+            ),
+        }
+    }
+}
+
+impl Display for BananaWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.warning_text())
+    }
+}
+
+/// Implemented in `no_std` builds too, `std::error::Error` is the same
+/// trait.
+///
+/// Errors wrapping another error have it as the source.
 impl Error for BananaError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
-        None
+        match self {
+            BananaError::IncompatibleCollections { reason }
+            | BananaError::ShareRejected { reason, .. } => Some(reason.as_ref()),
+            _ => None,
+        }
     }
 }
@@ -0,0 +1,272 @@
+//! C interface, for recovery from other languages.
+//!
+//! Collection and combined set are exposed as opaque handles. Collection is
+//! created with [`banana_collection_new`] and must be freed with
+//! [`banana_collection_free`]. Combined set pointer from
+//! [`banana_collection_combined`] is borrowed from the collection, and is
+//! valid until the collection is modified or freed.
+//!
+//! Functions returning `i32` return [`BANANA_OK`] on success, positive
+//! stable [`BananaError::code`](crate::BananaError::code) on protocol errors,
+//! and negative codes on interface misuse.
+//!
+//! Functions writing into caller-provided buffers write nul-terminated utf8.
+//! Needed buffer capacity, the data length with the terminating nul, is
+//! written into `needed`, unless it is null. If the buffer capacity is not
+//! enough, nothing is written into the buffer and [`BANANA_BUFFER_TOO_SMALL`]
+//! is returned; buffer could be null with zero capacity, to learn the needed
+//! capacity only. Data with nul bytes inside would be cut short by C string
+//! readers, so it is never written, and [`BANANA_INTERIOR_NUL`] is returned.
+//!
+//! Static library and C header could be made with:
+//!
+//! ```text
+//! cargo rustc --release --features ffi --crate-type staticlib
+//! cbindgen --config cbindgen.toml --output banana_recovery.h
+//! ```
+use alloc::{boxed::Box, ffi::CString, string::String};
+use core::ffi::{c_char, CStr};
+use core::{ptr, slice};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::shares::{SetCombined, Share, ShareCollection};
+
+/// Success.
+pub const BANANA_OK: i32 = 0;
+
+/// Null pointer passed where a valid one is required.
+pub const BANANA_NULL_POINTER: i32 = -1;
+
+/// Output buffer is too small.
+pub const BANANA_BUFFER_TOO_SMALL: i32 = -2;
+
+/// Collection has no title, it is empty.
+pub const BANANA_NO_TITLE: i32 = -3;
+
+/// Data has nul bytes inside, and is not representable as nul-terminated
+/// string.
+pub const BANANA_INTERIOR_NUL: i32 = -4;
+
+/// Collection status: no shares.
+pub const BANANA_STATUS_EMPTY: i32 = 0;
+
+/// Collection status: some shares collected, more are needed.
+pub const BANANA_STATUS_IN_PROGRESS: i32 = 1;
+
+/// Collection status: shares combined, secret could be recovered.
+pub const BANANA_STATUS_READY: i32 = 2;
+
+/// New empty share collection.
+///
+/// Collection must be freed with [`banana_collection_free`].
+#[no_mangle]
+pub extern "C" fn banana_collection_new() -> *mut ShareCollection {
+    Box::into_raw(Box::new(ShareCollection::new()))
+}
+
+/// Free share collection.
+///
+/// # Safety
+///
+/// `collection` must be null, or a pointer from [`banana_collection_new`]
+/// not freed before.
+#[no_mangle]
+pub unsafe extern "C" fn banana_collection_free(collection: *mut ShareCollection) {
+    if !collection.is_null() {
+        drop(Box::from_raw(collection));
+    }
+}
+
+/// Add share from QR code payload bytes to the collection.
+///
+/// # Safety
+///
+/// `collection` must be a valid collection pointer, `bytes` must point to
+/// `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn banana_collection_add_share(
+    collection: *mut ShareCollection,
+    bytes: *const u8,
+    len: usize,
+) -> i32 {
+    let Some(collection) = collection.as_mut() else {
+        return BANANA_NULL_POINTER;
+    };
+    if bytes.is_null() {
+        return BANANA_NULL_POINTER;
+    }
+    let payload = slice::from_raw_parts(bytes, len).to_vec();
+    match Share::new(payload).and_then(|share| collection.add_share(share)) {
+        Ok(_) => BANANA_OK,
+        Err(e) => i32::from(e.code()),
+    }
+}
+
+/// Collection status, one of `BANANA_STATUS_*` values.
+///
+/// # Safety
+///
+/// `collection` must be a valid collection pointer.
+#[no_mangle]
+pub unsafe extern "C" fn banana_collection_status(collection: *const ShareCollection) -> i32 {
+    match collection.as_ref() {
+        None => BANANA_NULL_POINTER,
+        Some(ShareCollection::Empty) => BANANA_STATUS_EMPTY,
+        Some(ShareCollection::InProgress(_)) => BANANA_STATUS_IN_PROGRESS,
+        Some(ShareCollection::Ready(_)) => BANANA_STATUS_READY,
+    }
+}
+
+/// Write collection title into the buffer.
+///
+/// # Safety
+///
+/// `collection` must be a valid collection pointer, `buf` must point to
+/// `cap` writable bytes, `needed` must be null or point to writable `usize`.
+#[no_mangle]
+pub unsafe extern "C" fn banana_collection_title(
+    collection: *const ShareCollection,
+    buf: *mut c_char,
+    cap: usize,
+    needed: *mut usize,
+) -> i32 {
+    let Some(collection) = collection.as_ref() else {
+        return BANANA_NULL_POINTER;
+    };
+    match collection.title() {
+        Some(title) => write_nul_terminated(title.as_bytes(), buf, cap, needed),
+        None => BANANA_NO_TITLE,
+    }
+}
+
+/// Combined set borrowed from the collection, or null if the collection is
+/// not ready.
+///
+/// Pointer is valid until the collection is modified or freed, and must not
+/// be freed on its own.
+///
+/// # Safety
+///
+/// `collection` must be null, or a valid collection pointer.
+#[no_mangle]
+pub unsafe extern "C" fn banana_collection_combined(
+    collection: *const ShareCollection,
+) -> *const SetCombined {
+    match collection.as_ref().and_then(ShareCollection::ready) {
+        Some(combined) => combined,
+        None => ptr::null(),
+    }
+}
+
+/// Recover the secret with nul-terminated passphrase, and write it into the
+/// buffer.
+///
+/// # Safety
+///
+/// `combined` must be a valid combined set pointer, `passphrase` must be a
+/// nul-terminated string, `out_buf` must point to `cap` writable bytes,
+/// `needed` must be null or point to writable `usize`.
+#[no_mangle]
+pub unsafe extern "C" fn banana_combined_recover(
+    combined: *const SetCombined,
+    passphrase: *const c_char,
+    out_buf: *mut c_char,
+    cap: usize,
+    needed: *mut usize,
+) -> i32 {
+    let Some(combined) = combined.as_ref() else {
+        return BANANA_NULL_POINTER;
+    };
+    if passphrase.is_null() {
+        return BANANA_NULL_POINTER;
+    }
+    let passphrase = CStr::from_ptr(passphrase).to_bytes();
+    match combined.recover_with_passphrase_bytes(passphrase) {
+        Ok(secret) => {
+            let secret = Zeroizing::new(secret);
+            write_nul_terminated(secret.as_bytes(), out_buf, cap, needed)
+        }
+        Err(e) => i32::from(e.code()),
+    }
+}
+
+/// Recover the secret with nul-terminated passphrase, as newly allocated
+/// nul-terminated string.
+///
+/// Returns null if the recovery failed. Secret must be freed with
+/// [`banana_string_free`].
+///
+/// # Safety
+///
+/// `combined` must be a valid combined set pointer, `passphrase` must be a
+/// nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn banana_combined_recover_string(
+    combined: *const SetCombined,
+    passphrase: *const c_char,
+) -> *mut c_char {
+    let Some(combined) = combined.as_ref() else {
+        return ptr::null_mut();
+    };
+    if passphrase.is_null() {
+        return ptr::null_mut();
+    }
+    let passphrase = CStr::from_ptr(passphrase).to_bytes();
+    match combined
+        .recover_with_passphrase_bytes(passphrase)
+        .map(String::into_bytes)
+        .map(CString::new)
+    {
+        Ok(Ok(secret)) => secret.into_raw(),
+        Ok(Err(e)) => {
+            // secret with nul byte inside is not representable
+            e.into_vec().zeroize();
+            ptr::null_mut()
+        }
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Free string returned by the library. String is zeroized before freeing.
+///
+/// # Safety
+///
+/// `string` must be null, or a string pointer returned by the library not
+/// freed before.
+#[no_mangle]
+pub unsafe extern "C" fn banana_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        CString::from_raw(string).into_bytes_with_nul().zeroize();
+    }
+}
+
+/// Copy data and terminating nul into the buffer, if it fits, and report the
+/// needed capacity.
+///
+/// # Safety
+///
+/// `buf` must point to `cap` writable bytes, `needed` must be null or point
+/// to writable `usize`.
+unsafe fn write_nul_terminated(
+    data: &[u8],
+    buf: *mut c_char,
+    cap: usize,
+    needed: *mut usize,
+) -> i32 {
+    if data.contains(&0) {
+        return BANANA_INTERIOR_NUL;
+    }
+    if let Some(needed) = needed.as_mut() {
+        *needed = data.len() + 1;
+    }
+    if data.len() >= cap {
+        return BANANA_BUFFER_TOO_SMALL;
+    }
+    if buf.is_null() {
+        return BANANA_NULL_POINTER;
+    }
+    let out = slice::from_raw_parts_mut(buf.cast::<u8>(), cap);
+    out[..data.len()].copy_from_slice(data);
+    out[data.len()] = 0;
+    BANANA_OK
+}
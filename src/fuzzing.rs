@@ -0,0 +1,137 @@
+//! Payloads for fuzzing the share parsing, collecting and combining.
+use alloc::{string::String, vec, vec::Vec};
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::shares::Share;
+
+/// Radix36 chars, all the possible bits chars and then some.
+const BITS_CHARS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz!";
+
+/// Maximum number of companion shares in [`FuzzSet`], so that combining
+/// stays fast.
+const MAX_COMPANIONS: usize = 16;
+
+/// QR payload for fuzzing.
+///
+/// Generated payloads are either random bytes, or share json with random
+/// values, that is far more likely to get deep into the share processing.
+/// Json could have extra fields and garbage around it.
+///
+/// [`Share::new`](crate::Share::new) processes any payload without
+/// panicking.
+#[derive(Clone, Debug)]
+pub struct FuzzPayload(pub Vec<u8>);
+
+impl<'a> Arbitrary<'a> for FuzzPayload {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        if u.arbitrary()? {
+            return Ok(Self(u.arbitrary()?));
+        }
+
+        // values are mostly the valid ones, to get deep into the processing
+        let mut json = String::from("{");
+        let hex_body = match u.int_in_range(0..=7)? {
+            0 => true,
+            1 => {
+                json.push_str(&format!(r#""v":{},"#, u.arbitrary::<u8>()?));
+                false
+            }
+            2 => {
+                json.push_str(&format!(r#""v":{},"#, quoted(u.arbitrary()?)));
+                false
+            }
+            _ => {
+                json.push_str(r#""v":1,"#);
+                false
+            }
+        };
+        json.push_str(&format!(r#""t":{},"#, quoted(u.arbitrary()?)));
+        let required_shares = if u.ratio(3, 4)? {
+            u.int_in_range(2..=4u32)?
+        } else {
+            u.int_in_range(0..=300u32)?
+        };
+        json.push_str(&format!(r#""r":{},"#, required_shares));
+
+        // share data, bits char and body
+        let mut d = String::new();
+        if u.ratio(3, 4)? {
+            d.push('8');
+        } else {
+            d.push(*u.choose(BITS_CHARS)? as char);
+        }
+        let body: Vec<u8> = u.arbitrary()?;
+        if hex_body ^ u.ratio(1, 8)? {
+            d.push_str(&hex::encode(body));
+        } else {
+            d.push_str(&base64::encode(body));
+        }
+        json.push_str(&format!(r#""d":{},"#, quoted(&d)));
+
+        // nonce of proper length most of the time
+        let nonce: Vec<u8> = if u.ratio(7, 8)? {
+            u.arbitrary::<[u8; 24]>()?.to_vec()
+        } else {
+            u.arbitrary()?
+        };
+        json.push_str(&format!(r#""n":"{}""#, base64::encode(nonce)));
+
+        if u.ratio(1, 8)? {
+            json.push_str(&format!(r#","x":{}"#, quoted(u.arbitrary()?)));
+        }
+        json.push('}');
+
+        let mut payload = json.into_bytes();
+        if u.ratio(1, 8)? {
+            payload.extend_from_slice(u.arbitrary()?);
+        }
+        Ok(Self(payload))
+    }
+}
+
+/// QR payloads of a share set, for fuzzing the collecting and combining.
+///
+/// First payload is a [`FuzzPayload`]. If it is a valid share, payloads of
+/// companion shares follow: same set, random ids and content, enough to get
+/// the set combined. Companions could repeat ids or content, and could be
+/// damaged.
+#[derive(Clone, Debug)]
+pub struct FuzzSet {
+    /// Shares QR payloads.
+    pub payloads: Vec<Vec<u8>>,
+
+    /// Id of the share to derive from the combined set.
+    pub new_id: u32,
+}
+
+impl<'a> Arbitrary<'a> for FuzzSet {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let first = FuzzPayload::arbitrary(u)?.0;
+        let mut payloads = Vec::new();
+        if let Ok(share) = Share::new(first.clone()) {
+            let max_id = 2u32.pow(share.bits()) - 1;
+            let companions = share.required_shares().saturating_sub(1) + u.int_in_range(0..=2)?;
+            for _ in 0..companions.min(MAX_COMPANIONS) {
+                let id = u.int_in_range(1..=max_id)?;
+                let mut content = vec![0; share.content_len()];
+                u.fill_buffer(&mut content)?;
+                let mut payload = share.companion(id, content).to_qr_payload();
+                if u.ratio(1, 16)? {
+                    let position = u.choose_index(payload.len())?;
+                    payload[position] = u.arbitrary()?;
+                }
+                payloads.push(payload);
+            }
+        }
+        payloads.insert(0, first);
+        Ok(Self {
+            payloads,
+            new_id: u.arbitrary()?,
+        })
+    }
+}
+
+/// Json string with given content.
+fn quoted(content: &str) -> String {
+    serde_json::to_string(content).expect("string is always serializable")
+}
@@ -3,6 +3,8 @@
 //!
 //! This crate is `no_std` compatible in `default-features = false` mode.
 //!
+//! Minimum supported Rust version is 1.81, for `core::error::Error`.
+//!
 //! # Examples
 //!```
 //! # #[cfg(feature = "std")]
@@ -20,44 +22,66 @@
 //! let mut share_collection = ShareCollection::new();
 //!
 //! // Process share #1 QR code.
-//! let share1 = Share::new(hex::decode(SCAN_A1).unwrap()).unwrap();
+//! let share1 = Share::from_hex_payload(SCAN_A1).unwrap();
 //!
 //! // Add share #1 to collector.
 //! share_collection.add_share(share1).unwrap();
 //!
-//! if let ShareCollection::InProgress(ref in_progress) = share_collection {
-//!     // 1 share collected so far
-//!     assert_eq!(in_progress.shares_now(), 1);
+//! // 1 share collected so far
+//! assert_eq!(share_collection.shares_now(), 1);
+//!
+//! // minimim 2 shares are needed (info from share itself)
+//! assert_eq!(share_collection.shares_required(), Some(2));
+//!
+//! // set title, identical for all shares in set (info from share itself)
+//! assert_eq!(share_collection.title(), Some("Alice tries BananaSplit again"));
 //!
-//!     // minimim 2 shares are needed (info from share itself)
-//!     assert_eq!(in_progress.shares_required(), 2);
+//! // not enough shares yet, collector is in progress
+//! assert!(!share_collection.is_ready());
+//! let in_progress = share_collection
+//!     .in_progress()
+//!     .expect("Added 1 share out of required 2. Must be `InProgress` variant.");
 //!
-//!     // set title, identical for all shares in set (info from share itself)
-//!     assert_eq!(in_progress.title(), "Alice tries BananaSplit again");
-//! } else {
-//!     panic!("Added 1 share out of required 2. Must be `InProgress` variant.")
-//! }
+//! // 1 more share is needed
+//! assert_eq!(in_progress.shares_missing(), 1);
+//!
+//! // share #1 is already scanned
+//! assert_eq!(in_progress.ids(), &[1]);
+//! assert!(in_progress.contains_id(1));
+//!
+//! // or, all of the above in one go
+//! let progress = in_progress.progress();
+//! assert_eq!(progress.collected, 1);
+//! assert_eq!(progress.missing, 1);
 //!
 //! // Try adding same share #1 again. This would result in an error.
-//! let share1_again = Share::new(hex::decode(SCAN_A1).unwrap()).unwrap();
+//! let share1_again = Share::from_hex_payload(SCAN_A1).unwrap();
 //! assert!(
 //!     share_collection.add_share(share1_again).is_err(),
 //!     "Can not add the same share second time."
 //! );
 //!
 //! // Process share #2 QR code.
-//! let share2 = Share::new(hex::decode(SCAN_A2).unwrap()).unwrap();
+//! let share2 = Share::from_hex_payload(SCAN_A2).unwrap();
 //!
 //! // Add share #2 to collector.
 //! share_collection.add_share(share2).unwrap();
 //!
-//! if let ShareCollection::Ready(combined) = share_collection {
-//!     // `SetCombined` could be processed to recover the secret.
-//!     let alice_secret = combined.recover_with_passphrase(PASSPHRASE_A).unwrap();
-//!     assert_eq!(alice_secret, ALICE_SEEDPHRASE);
-//! } else {
-//!     panic!("Added 2 different shares out of required 2. Must be `Ready` variant.")
-//! }
+//! // Shares #1 and #2 were used.
+//! assert_eq!(share_collection.ids(), &[1, 2]);
+//!
+//! // `SetCombined` could be processed to recover the secret.
+//! assert!(share_collection.is_ready());
+//! let combined = share_collection
+//!     .take_ready()
+//!     .expect("Added 2 different shares out of required 2. Must be `Ready` variant.");
+//! let alice_secret = combined.recover_with_passphrase(PASSPHRASE_A).unwrap();
+//! assert_eq!(alice_secret, ALICE_SEEDPHRASE);
+//!
+//! // Alternatively, if all payloads are available at once, recover in one call.
+//! let payloads = vec![hex::decode(SCAN_A1).unwrap(), hex::decode(SCAN_A2).unwrap()];
+//! let alice_secret = banana_recovery::recover(&payloads, PASSPHRASE_A).unwrap();
+//! assert_eq!(alice_secret, ALICE_SEEDPHRASE);
 //! # }
 //! ```
 #![no_std]
@@ -73,11 +97,52 @@ extern crate core;
 #[cfg(feature = "std")]
 extern crate std;
 
+#[cfg(feature = "std")]
+mod archive;
+#[cfg(feature = "compression")]
+mod compression;
+mod dedup;
 mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "arbitrary")]
+mod fuzzing;
+#[cfg(feature = "uniffi")]
+mod mobile;
+pub mod passphrase;
+#[cfg(feature = "serde")]
+mod serialization;
 mod shares;
+mod split;
+mod verify;
+#[cfg(feature = "wasm")]
+mod wasm;
 
 #[cfg(test)]
 mod tests;
 
-pub use error::BananaError;
-pub use shares::{SetCombined, SetInProgress, Share, ShareCollection};
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!();
+
+#[cfg(feature = "std")]
+pub use archive::parse_archive;
+pub use dedup::ScanDeduplicator;
+pub use error::{BananaError, BananaWarning, ErrorKind, ERROR_CODES, SHARE_REJECTED_CODE_OFFSET};
+#[cfg(feature = "arbitrary")]
+pub use fuzzing::{FuzzPayload, FuzzSet};
+#[cfg(feature = "uniffi")]
+pub use mobile::{MobileError, MobileSetCombined, MobileShare, MobileShareCollection};
+pub use scrypt::Params as ScryptParams;
+#[cfg(feature = "std")]
+pub use shares::PROGRESS_HEARTBEAT;
+pub use shares::{
+    combine_collections, detect, max_distributable_shares, recommend_bits, recover,
+    scrypt_memory_bytes, version_histogram, AddOutcome, ConsistencyReport, DetectedShare,
+    MultiShareCollection, ParseOptions, Progress, RecoveryKey, RecoverySession, SecretKind,
+    SecretSink, SetCombined, SetInProgress, Share, ShareCollection, ShareDiff, ShareMismatch,
+    Sufficiency, Version, BIT_RANGE, DEFAULT_MAX_CONTENT_LENGTH, MAX_CONSISTENCY_SUBSETS, V1_BITS,
+};
+pub use split::split_secret;
+pub use verify::verify_vectors;
+#[cfg(feature = "wasm")]
+pub use wasm::JsShareCollection;
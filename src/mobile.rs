@@ -0,0 +1,199 @@
+//! Bindings for mobile apps, with UniFFI.
+//!
+//! Interface follows the Rust one: share is made from QR code payload bytes,
+//! added to the collection, and once the collection is ready, combined set
+//! recovers the secret with passphrase.
+//!
+//! Errors are flattened into [`MobileError`], by [`ErrorKind`], with stable
+//! error code and error text.
+//!
+//! Kotlin bindings are generated from the library built with the `uniffi`
+//! feature:
+//!
+//! ```text
+//! cargo rustc --release --features uniffi --crate-type cdylib
+//! cargo run --features uniffi-cli --example uniffi-bindgen -- generate \
+//!     --library target/release/libbanana_recovery.so --language kotlin --out-dir out
+//! ```
+//!
+//! Generated bindings are exercised by `examples/kotlin/smoke.kts`.
+use alloc::{
+    string::{String, ToString},
+    sync::Arc,
+    vec::Vec,
+};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use crate::error::{BananaError, ErrorKind};
+use crate::shares::{SetCombined, Share, ShareCollection};
+
+/// Error, flattened for the bindings.
+///
+/// Variant is [`ErrorKind`] of the error. `code` is
+/// [`BananaError::code`], `text` is the error text.
+#[derive(Debug, uniffi::Error)]
+pub enum MobileError {
+    /// [`ErrorKind::InvalidShare`].
+    InvalidShare {
+        /// Error code.
+        code: u16,
+        /// Error text.
+        text: String,
+    },
+
+    /// [`ErrorKind::IncompatibleShare`].
+    IncompatibleShare {
+        /// Error code.
+        code: u16,
+        /// Error text.
+        text: String,
+    },
+
+    /// [`ErrorKind::CorruptedSet`].
+    CorruptedSet {
+        /// Error code.
+        code: u16,
+        /// Error text.
+        text: String,
+    },
+
+    /// [`ErrorKind::DecryptionFailed`].
+    DecryptionFailed {
+        /// Error code.
+        code: u16,
+        /// Error text.
+        text: String,
+    },
+
+    /// [`ErrorKind::Internal`].
+    Internal {
+        /// Error code.
+        code: u16,
+        /// Error text.
+        text: String,
+    },
+}
+
+impl From<BananaError> for MobileError {
+    fn from(e: BananaError) -> Self {
+        let code = e.code();
+        let text = e.to_string();
+        match e.kind() {
+            ErrorKind::InvalidShare => MobileError::InvalidShare { code, text },
+            ErrorKind::IncompatibleShare => MobileError::IncompatibleShare { code, text },
+            ErrorKind::CorruptedSet => MobileError::CorruptedSet { code, text },
+            ErrorKind::DecryptionFailed => MobileError::DecryptionFailed { code, text },
+            ErrorKind::Internal => MobileError::Internal { code, text },
+        }
+    }
+}
+
+impl core::fmt::Display for MobileError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            MobileError::InvalidShare { text, .. }
+            | MobileError::IncompatibleShare { text, .. }
+            | MobileError::CorruptedSet { text, .. }
+            | MobileError::DecryptionFailed { text, .. }
+            | MobileError::Internal { text, .. } => f.write_str(text),
+        }
+    }
+}
+
+/// Share, for the bindings.
+#[derive(Debug, uniffi::Object)]
+pub struct MobileShare(Share);
+
+#[uniffi::export]
+impl MobileShare {
+    /// Share from QR code payload bytes.
+    #[uniffi::constructor]
+    pub fn new(payload: Vec<u8>) -> Result<Arc<Self>, MobileError> {
+        Ok(Arc::new(Self(Share::new(payload)?)))
+    }
+
+    /// Share title.
+    pub fn title(&self) -> String {
+        self.0.title().to_string()
+    }
+
+    /// Share id.
+    pub fn id(&self) -> u32 {
+        self.0.id()
+    }
+
+    /// Number of shares required to combine the set.
+    pub fn required_shares(&self) -> u32 {
+        self.0.required_shares() as u32
+    }
+}
+
+/// Shares collector, for the bindings.
+#[derive(Debug, Default, uniffi::Object)]
+pub struct MobileShareCollection(Mutex<ShareCollection>);
+
+#[uniffi::export]
+impl MobileShareCollection {
+    /// Initiate new share collecting.
+    #[uniffi::constructor]
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Add a share to the collection.
+    pub fn add_share(&self, share: Arc<MobileShare>) -> Result<(), MobileError> {
+        let _ = self.collection().add_share(share.0.clone())?;
+        Ok(())
+    }
+
+    /// Number of shares collected so far.
+    pub fn shares_now(&self) -> u32 {
+        self.collection().shares_now() as u32
+    }
+
+    /// Number of shares required, if any shares are collected.
+    pub fn shares_required(&self) -> Option<u32> {
+        self.collection()
+            .shares_required()
+            .map(|required| required as u32)
+    }
+
+    /// Set title, if any shares are collected.
+    pub fn title(&self) -> Option<String> {
+        self.collection().title().map(ToString::to_string)
+    }
+
+    /// Shares are combined, and secret could be recovered.
+    pub fn is_ready(&self) -> bool {
+        self.collection().is_ready()
+    }
+
+    /// Combined set, if the collection is ready.
+    pub fn combined(&self) -> Option<Arc<MobileSetCombined>> {
+        self.collection()
+            .ready()
+            .map(|combined| Arc::new(MobileSetCombined(combined.clone())))
+    }
+}
+
+impl MobileShareCollection {
+    /// Locked collection.
+    ///
+    /// Collection is never left inconsistent, so it is used even if a thread
+    /// panicked while holding the lock.
+    fn collection(&self) -> MutexGuard<'_, ShareCollection> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Combined shares data, for the bindings.
+#[derive(Debug, uniffi::Object)]
+pub struct MobileSetCombined(SetCombined);
+
+#[uniffi::export]
+impl MobileSetCombined {
+    /// Recover the secret with user-provided passphrase.
+    pub fn recover_with_passphrase(&self, passphrase: String) -> Result<String, MobileError> {
+        Ok(self.0.recover_with_passphrase(&passphrase)?)
+    }
+}
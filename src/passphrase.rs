@@ -0,0 +1,164 @@
+//! Passphrase normalization, for typo-tolerant recovery.
+use alloc::{string::String, vec::Vec};
+use zeroize::Zeroizing;
+
+#[cfg(feature = "generator")]
+use rand_core::{CryptoRng, RngCore};
+
+/// Normalize passphrase typed by a human.
+///
+/// Passphrase is trimmed, lowercased, and whitespace runs between the words
+/// are replaced with single hyphens, so that
+/// `" Blighted comprised  bucktooth disjoin "` becomes
+/// `"blighted-comprised-bucktooth-disjoin"`.
+pub fn normalize(passphrase: &str) -> String {
+    Zeroizing::new(hyphenate(passphrase)).to_lowercase()
+}
+
+/// Trim the passphrase and replace whitespace runs with single hyphens,
+/// keeping the case.
+fn hyphenate(passphrase: &str) -> String {
+    passphrase
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join("-")
+}
+
+/// Variant of the passphrase tried in
+/// [`SetCombined::recover_with_candidates`](crate::SetCombined::recover_with_candidates).
+///
+/// Variants are tried in the order listed here. Variant identical to one
+/// tried before is skipped, and does not count as an attempt.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PassphraseVariant {
+    /// Passphrase exactly as typed.
+    AsTyped,
+
+    /// Passphrase with leading and trailing whitespace removed.
+    Trimmed,
+
+    /// Trimmed passphrase with whitespace runs replaced by single hyphens,
+    /// case kept as typed.
+    Hyphenated,
+
+    /// Passphrase processed with [`normalize`].
+    Normalized,
+}
+
+/// Options for
+/// [`SetCombined::recover_with_candidates`](crate::SetCombined::recover_with_candidates).
+#[derive(Clone, Copy, Debug)]
+pub struct CandidateOptions {
+    /// Maximum number of passphrase variants tried. Each attempt is a full
+    /// scrypt key derivation.
+    pub max_attempts: usize,
+}
+
+impl Default for CandidateOptions {
+    /// All the variants are tried.
+    fn default() -> Self {
+        Self { max_attempts: 4 }
+    }
+}
+
+/// Passphrase variants to try, in fixed order:
+///
+/// 1. [`PassphraseVariant::AsTyped`]
+/// 2. [`PassphraseVariant::Trimmed`]
+/// 3. [`PassphraseVariant::Hyphenated`]
+/// 4. [`PassphraseVariant::Normalized`]
+///
+/// Variants identical to the ones before are skipped and do not count
+/// towards `max_attempts`.
+pub(crate) fn candidates(
+    passphrase: &str,
+    options: &CandidateOptions,
+) -> Vec<(PassphraseVariant, Zeroizing<String>)> {
+    let all = [
+        (
+            PassphraseVariant::AsTyped,
+            Zeroizing::new(String::from(passphrase)),
+        ),
+        (
+            PassphraseVariant::Trimmed,
+            Zeroizing::new(String::from(passphrase.trim())),
+        ),
+        (
+            PassphraseVariant::Hyphenated,
+            Zeroizing::new(hyphenate(passphrase)),
+        ),
+        (
+            PassphraseVariant::Normalized,
+            Zeroizing::new(normalize(passphrase)),
+        ),
+    ];
+    let mut candidates: Vec<(PassphraseVariant, Zeroizing<String>)> = Vec::new();
+    for (variant, candidate) in all {
+        if candidates.len() == options.max_attempts {
+            break;
+        }
+        if !candidates.iter().any(|(_, known)| known == &candidate) {
+            candidates.push((variant, candidate));
+        }
+    }
+    candidates
+}
+
+/// EFF large wordlist, one word per line.
+///
+/// From <https://www.eff.org/deeplinks/2016/07/new-wordlists-random-passphrases>,
+/// same wordlist as the one used by banana split passphrase generator.
+#[cfg(feature = "generator")]
+const WORDLIST: &str = include_str!("eff_large_wordlist.txt");
+
+/// Number of words in the wordlist used by [`generate`].
+///
+/// EFF wordlist has 7776 words, hyphenated words `drop-down`, `felt-tip`,
+/// `t-shirt` and `yo-yo` are not used.
+#[cfg(feature = "generator")]
+pub const WORDLIST_LENGTH: u32 = 7772;
+
+/// Words from the wordlist that could be used in hyphen-joined passphrase.
+///
+/// Words containing hyphens would make the number of words in generated
+/// passphrase ambiguous.
+#[cfg(feature = "generator")]
+fn generator_words() -> impl Iterator<Item = &'static str> {
+    WORDLIST.lines().filter(|word| !word.contains('-'))
+}
+
+/// Generate passphrase of given number of words, in banana split style.
+///
+/// Words are picked uniformly from the EFF large wordlist, without the
+/// hyphenated words, of [`WORDLIST_LENGTH`] words, i.e. each word adds
+/// `log2(7772) ≈ 12.9` bits of entropy, and are joined with hyphens, for example
+/// `blighted-comprised-bucktooth-disjoin`. Banana split uses 4 words, about
+/// 51.7 bits of entropy.
+#[cfg(feature = "generator")]
+pub fn generate(words: usize, rng: &mut (impl RngCore + CryptoRng)) -> String {
+    let mut picked: Vec<&str> = Vec::with_capacity(words);
+    for _ in 0..words {
+        let index = random_index(rng) as usize;
+        picked.push(
+            generator_words()
+                .nth(index)
+                .expect("index is below static wordlist length"),
+        );
+    }
+    picked.join("-")
+}
+
+/// Uniformly distributed random wordlist index.
+///
+/// Values from the incomplete last span of `u32` are rejected, so that no
+/// word is more likely than others.
+#[cfg(feature = "generator")]
+fn random_index(rng: &mut (impl RngCore + CryptoRng)) -> u32 {
+    let zone = u32::MAX - u32::MAX % WORDLIST_LENGTH;
+    loop {
+        let value = rng.next_u32();
+        if value < zone {
+            return value % WORDLIST_LENGTH;
+        }
+    }
+}
@@ -0,0 +1,91 @@
+//! Serialization of shares and share sets, for persisting partial scanning
+//! state.
+//!
+//! Share is serialized as its json, as in share QR code. Sets and collectors
+//! are serialized as sequences of shares. On deserialization the shares are
+//! processed same way as the scanned ones, so all checks are done again, and
+//! `SetCombined` is combined anew.
+use alloc::{string::String, vec::Vec};
+
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::error::BananaError;
+use crate::shares::{SetCombined, SetInProgress, Share, ShareCollection};
+
+impl Serialize for Share {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_json())
+    }
+}
+
+impl<'de> Deserialize<'de> for Share {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let share_json = String::deserialize(deserializer)?;
+        Share::new(share_json.into_bytes()).map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for SetInProgress {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.view().shares().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SetInProgress {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let shares = Vec::<Share>::deserialize(deserializer)?;
+        set_from_shares(shares).map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for SetCombined {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.view().shares().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SetCombined {
+    /// Combined set is restored by combining the shares with the built-in
+    /// primitive polynomials.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let set = SetInProgress::deserialize(deserializer)?;
+        set.check_threshold().map_err(D::Error::custom)?;
+        set.combine().map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for ShareCollection {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ShareCollection::Empty => Vec::<Share>::new().serialize(serializer),
+            ShareCollection::InProgress(in_progress) => in_progress.serialize(serializer),
+            ShareCollection::Ready(combined) => combined.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ShareCollection {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let shares = Vec::<Share>::deserialize(deserializer)?;
+        let mut share_collection = ShareCollection::new();
+        for share in shares.into_iter() {
+            let _ = share_collection
+                .add_share(share)
+                .map_err(D::Error::custom)?;
+        }
+        Ok(share_collection)
+    }
+}
+
+/// Set of compatible shares.
+fn set_from_shares(shares: Vec<Share>) -> Result<SetInProgress, BananaError> {
+    let mut shares = shares.into_iter();
+    let mut set = match shares.next() {
+        Some(a) => SetInProgress::init(a),
+        None => return Err(BananaError::NoShares),
+    };
+    for share in shares {
+        set.add_share(share)?;
+    }
+    Ok(set)
+}
@@ -1,16 +1,39 @@
 //! Shares processing.
-use alloc::{borrow::ToOwned, string::String, vec::Vec};
-use core::{convert::TryInto, ops::RangeInclusive};
-
+use alloc::{
+    borrow::ToOwned,
+    boxed::Box,
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{
+    fmt::{Debug, Display, Formatter, Result as FmtResult},
+    ops::RangeInclusive,
+    str::FromStr,
+};
+
+use base64::{
+    alphabet,
+    engine::{
+        fast_portable::{self, FastPortable},
+        DecodePaddingMode,
+    },
+};
 use bitvec::prelude::{BitVec, Msb0};
+use once_cell::race::OnceBox;
+use rand_core::{CryptoRng, RngCore};
 use scrypt::{scrypt, Params};
-use serde::Deserialize;
-use sha2::{Digest, Sha512};
+use serde::{
+    de::{Error as DeError, Unexpected, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
+use sha2::{Digest, Sha256, Sha512};
 use xsalsa20poly1305::aead::{generic_array::GenericArray, Aead, KeyInit};
 use xsalsa20poly1305::XSalsa20Poly1305;
-use zeroize::Zeroize;
+use zeroize::{Zeroize, Zeroizing};
 
-use crate::error::BananaError;
+use crate::error::{BananaError, BananaWarning, ErrorKind};
+use crate::passphrase::{candidates, CandidateOptions, PassphraseVariant};
 
 /// Allowed range for bits value.
 ///
@@ -21,25 +44,172 @@ use crate::error::BananaError;
 /// Currently existing banana split version `V1` sets bits value to `8`.
 pub const BIT_RANGE: RangeInclusive<u32> = 3..=20;
 
+/// Nonce length for XSalsa20Poly1305, in bytes.
+pub(crate) const NONCE_LENGTH: usize = 24;
+
+/// Poly1305 authentication tag length, minimal length of encrypted data.
+pub(crate) const TAG_LENGTH: usize = 16;
+
+/// Bits value used in all known version `V1` shares.
+///
+/// Shares made with other bits values are valid, however, some generators
+/// may be unable to process them. Should be preferred unless the set must
+/// contain more than `255` shares.
+pub const V1_BITS: u32 = 8;
+
+/// Maximum number of distinct shares in the set with given bits value.
+///
+/// Share ids are non-zero elements of `GF(2^bits)`, so there could be at most
+/// `2^bits - 1` shares. Bits values outside of `BIT_RANGE` result in
+/// [`BananaError::BitsOutOfRange`].
+pub fn max_distributable_shares(bits: u32) -> Result<u32, BananaError> {
+    if BIT_RANGE.contains(&bits) {
+        Ok(2u32.pow(bits) - 1)
+    } else {
+        Err(BananaError::BitsOutOfRange(bits))
+    }
+}
+
+/// Smallest bits value in `BIT_RANGE`, sufficient for `total_shares` distinct
+/// shares.
+///
+/// If `total_shares` exceeds the limit for the largest bits value, the
+/// largest bits value is returned, and the caller should check it with
+/// [`max_distributable_shares`].
+pub fn recommend_bits(total_shares: usize) -> u32 {
+    for bits in BIT_RANGE {
+        if (2usize.pow(bits) - 1) >= total_shares {
+            return bits;
+        }
+    }
+    *BIT_RANGE.end()
+}
+
 /// Individual share data, successfully constructed only if corresponding json
 /// contains valid values.
 ///
 /// Constructed from the incoming QR data only. Bits are checked to be within
 /// `BIT_RANGE` allowed limits.
-#[derive(Debug)]
+///
+/// `Debug` output does not include share content and nonce.
+///
+/// Shares are equal if all the share data is equal: version, title, required
+/// shares, nonce, bits, id, and content. Two damaged variants of the share
+/// with the same id are not equal. Hashing is consistent with equality, so
+/// repeatedly scanned shares could be deduplicated in a `HashSet` before
+/// adding them to a set.
+///
+/// Content and nonce are zeroized on drop, in clones as well.
+#[derive(Clone, Eq, Hash, PartialEq)]
 pub struct Share {
     version: Version,
     title: String,
     required_shares: usize,
-    nonce: String,
+    nonce: Vec<u8>,
     bits: u32,
     id: u32,
     content: Vec<u8>,
 }
 
+/// Differences between two shares, from [`Share::diff`].
+///
+/// Boolean fields are `true` if the corresponding values differ.
+#[derive(Debug, Eq, PartialEq)]
+pub struct ShareDiff {
+    /// Protocol version.
+    pub version: bool,
+
+    /// Title.
+    pub title: bool,
+
+    /// Required number of shares.
+    pub required_shares: bool,
+
+    /// Nonce.
+    pub nonce: bool,
+
+    /// Bits value.
+    pub bits: bool,
+
+    /// Share id.
+    pub id: bool,
+
+    /// Offset of the first differing content byte, if content differs.
+    ///
+    /// If one content is a prefix of the other, offset is the length of the
+    /// shorter one.
+    pub content_offset: Option<usize>,
+}
+
+impl ShareDiff {
+    /// Shares are identical.
+    pub fn is_identical(&self) -> bool {
+        !(self.version
+            || self.title
+            || self.required_shares
+            || self.nonce
+            || self.bits
+            || self.id
+            || self.content_offset.is_some())
+    }
+}
+
+/// Everything that prevents a share from being added to a set, from
+/// [`SetInProgress::mismatch`].
+///
+/// Boolean fields are `true` if the corresponding condition is violated.
+/// Unlike errors from [`SetInProgress::would_accept`], all conditions are
+/// checked, which helps with sorting out mixed-up shares of different sets.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ShareMismatch {
+    /// Protocol version differs.
+    pub version: bool,
+
+    /// Title differs.
+    pub title: bool,
+
+    /// Required number of shares differs.
+    pub required_shares: bool,
+
+    /// Nonce differs.
+    pub nonce: bool,
+
+    /// Bits value differs.
+    pub bits: bool,
+
+    /// Share with same id is already in set.
+    pub already_in_set: bool,
+
+    /// Content length differs.
+    pub content_length: bool,
+
+    /// Content is identical to that of a share with different id already in
+    /// set.
+    pub content_duplicate: bool,
+}
+
+impl ShareMismatch {
+    /// Share could be added to the set.
+    pub fn is_compatible(&self) -> bool {
+        !(self.version
+            || self.title
+            || self.required_shares
+            || self.nonce
+            || self.bits
+            || self.already_in_set
+            || self.content_length
+            || self.content_duplicate)
+    }
+}
+
 /// Raw share data, as recovered from json.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 struct ShareJson {
+    #[serde(
+        default,
+        deserialize_with = "deserialize_version",
+        skip_serializing_if = "Option::is_none"
+    )]
     v: Option<u8>,
     t: String,
     r: usize,
@@ -47,6 +217,108 @@ struct ShareJson {
     n: String,
 }
 
+/// Raw share data, as recovered from json in strict mode.
+///
+/// Same as [`ShareJson`], but with no unknown fields allowed.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StrictShareJson {
+    #[serde(default, deserialize_with = "deserialize_version")]
+    v: Option<u8>,
+    t: String,
+    r: usize,
+    d: String,
+    n: String,
+}
+
+impl From<StrictShareJson> for ShareJson {
+    fn from(strict: StrictShareJson) -> Self {
+        Self {
+            v: strict.v,
+            t: strict.t,
+            r: strict.r,
+            d: strict.d,
+            n: strict.n,
+        }
+    }
+}
+
+/// Deserialize version number in share json, given either as a number or
+/// as a numeric string, i.e. both `"v":1` and `"v":"1"` are accepted.
+///
+/// Shares re-typed by hand or passed through spreadsheet tools could end up
+/// with quoted version.
+fn deserialize_version<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u8>, D::Error> {
+    deserializer.deserialize_option(VersionVisitor)
+}
+
+/// Visitor for version number in share json.
+struct VersionVisitor;
+
+impl<'de> Visitor<'de> for VersionVisitor {
+    type Value = Option<u8>;
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str("version number, as a number or a numeric string")
+    }
+
+    fn visit_none<E: DeError>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_unit<E: DeError>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_u64<E: DeError>(self, v: u64) -> Result<Self::Value, E> {
+        u8::try_from(v)
+            .map(Some)
+            .map_err(|_| E::invalid_value(Unexpected::Unsigned(v), &self))
+    }
+
+    fn visit_str<E: DeError>(self, v: &str) -> Result<Self::Value, E> {
+        v.parse::<u8>()
+            .map(Some)
+            .map_err(|_| E::invalid_value(Unexpected::Str(v), &self))
+    }
+}
+
+/// Default maximum share content length, in bytes.
+///
+/// Well above what a single QR code could physically hold.
+pub const DEFAULT_MAX_CONTENT_LENGTH: usize = 256 * 1024;
+
+/// Options for [`Share::new_with_options`].
+#[derive(Clone, Copy, Debug)]
+pub struct ParseOptions {
+    /// Maximum share content length, in bytes. Longer shares are rejected
+    /// with [`BananaError::ShareTooLarge`], before any content copies are
+    /// made.
+    pub max_content_length: usize,
+
+    /// Reject share json with unknown fields, and with anything at all
+    /// before or after the json object, including whitespace and the scanner
+    /// noise that is otherwise tolerated.
+    ///
+    /// Intended for security review, as extra data could be smuggled
+    /// through the QR code. Duplicate fields are rejected in any mode.
+    pub strict: bool,
+}
+
+impl Default for ParseOptions {
+    /// Lenient parsing, with [`DEFAULT_MAX_CONTENT_LENGTH`].
+    fn default() -> Self {
+        Self {
+            max_content_length: DEFAULT_MAX_CONTENT_LENGTH,
+            strict: false,
+        }
+    }
+}
+
 /// Version of banana split protocol.
 ///
 /// Currently only `V1` could be explicitly announced in share json.
@@ -54,33 +326,244 @@ struct ShareJson {
 /// No version provided in share json results in `Undefined` variant.
 ///
 /// Other versions are not supported and get rejected on [`Share`] construction.
-#[derive(Debug, Eq, PartialEq)]
+///
+///```
+/// use banana_recovery::{Share, Version};
+///
+/// let share = Share::from_json_str(
+///     r#"{"v":1,"t":"title","r":2,"d":"8AQID","n":"wLJ+K1f4VeIUxJjpQsolrHdrZIdZaeqv"}"#,
+/// )
+/// .unwrap();
+/// assert_eq!(share.version(), Version::V1);
+/// assert_eq!(share.version().as_u8(), Some(1));
+/// assert_eq!(share.version().to_string(), "v1");
+///```
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[non_exhaustive]
 pub enum Version {
+    /// No version in share json.
+    ///
+    /// Shares of banana split before the versioning was introduced. Share
+    /// body is in hexadecimal format, not in base64 as for `V1`, otherwise
+    /// shares are processed same way.
     Undefined,
+
+    /// Version `1` in share json.
     V1,
 }
 
+impl Version {
+    /// Version number as announced in share json, `None` for `Undefined`.
+    pub fn as_u8(&self) -> Option<u8> {
+        self.spec().number
+    }
+
+    /// Version number announced in share json is supported by this crate.
+    ///
+    /// Allows to pre-check a payload before processing it.
+    pub fn is_supported(number: u8) -> bool {
+        Self::from_number(Some(number)).is_ok()
+    }
+
+    /// Version from the number announced in share json, `None` if there was
+    /// no number.
+    fn from_number(number: Option<u8>) -> Result<Self, BananaError> {
+        match VERSION_SPECS.iter().find(|spec| spec.number == number) {
+            Some(spec) => Ok(spec.version),
+            None => Err(BananaError::VersionNotSupported(
+                number.expect("version without number is always supported"),
+            )),
+        }
+    }
+
+    /// Processing details of the version.
+    pub(crate) fn spec(&self) -> &'static VersionSpec {
+        VERSION_SPECS
+            .iter()
+            .find(|spec| spec.version == *self)
+            .expect("all versions are in the table")
+    }
+}
+
+/// Encoding of the share body, i.e. share data after the bits char.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum BodyEncoding {
+    /// Hexadecimal.
+    Hex,
+
+    /// Base64, in any of the [`BASE64_ENGINES`] flavors.
+    Base64,
+}
+
+/// Everything that depends on the protocol version, from share parsing to
+/// the key derivation.
+///
+/// Adding new version means adding a [`Version`] variant and its entry in
+/// [`VERSION_SPECS`].
+#[derive(Debug)]
+pub(crate) struct VersionSpec {
+    /// Version described.
+    pub(crate) version: Version,
+
+    /// Version number in share json, `None` if it is not announced.
+    pub(crate) number: Option<u8>,
+
+    /// Encoding of the share body.
+    pub(crate) body_encoding: BodyEncoding,
+
+    /// Nonce length, in bytes.
+    pub(crate) nonce_length: usize,
+
+    /// Bits value used by the known generators, others result in
+    /// [`BananaWarning::UnusualBitsForVersion`].
+    pub(crate) usual_bits: Option<u32>,
+
+    /// Scrypt parameters `log_n`, `r`, `p` for the key derivation.
+    pub(crate) scrypt: (u8, u32, u32),
+}
+
+/// All supported protocol versions.
+pub(crate) const VERSION_SPECS: [VersionSpec; 2] = [
+    VersionSpec {
+        version: Version::Undefined,
+        number: None,
+        body_encoding: BodyEncoding::Hex,
+        nonce_length: NONCE_LENGTH,
+        usual_bits: None,
+        scrypt: (15, 8, 1),
+    },
+    VersionSpec {
+        version: Version::V1,
+        number: Some(1),
+        body_encoding: BodyEncoding::Base64,
+        nonce_length: NONCE_LENGTH,
+        usual_bits: Some(V1_BITS),
+        scrypt: (15, 8, 1),
+    },
+];
+
+impl VersionSpec {
+    /// Decode the share body.
+    fn decode_body(&self, body: &str) -> Result<Vec<u8>, BananaError> {
+        match self.body_encoding {
+            BodyEncoding::Hex => hex::decode(body).map_err(|_| BananaError::UndefinedBodyNotHex),
+            BodyEncoding::Base64 => decode_base64(body).map_err(|_| BananaError::BodyNotBase64),
+        }
+    }
+
+    /// Length of the share body once decoded, if the body is validly
+    /// encoded.
+    fn decoded_body_length(&self, body: &str) -> usize {
+        match self.body_encoding {
+            BodyEncoding::Hex => body.len() / 2,
+            BodyEncoding::Base64 => body.trim_end_matches('=').len() * 3 / 4,
+        }
+    }
+
+    /// Encode the share body.
+    fn encode_body(&self, body: &[u8]) -> String {
+        match self.body_encoding {
+            BodyEncoding::Hex => hex::encode(body),
+            BodyEncoding::Base64 => base64::encode(body),
+        }
+    }
+
+    /// Scrypt parameters for the key derivation.
+    pub(crate) fn scrypt_params(&self) -> Params {
+        let (log_n, r, p) = self.scrypt;
+        Params::new(log_n, r, p).expect("static checked params")
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Version::Undefined => f.write_str("legacy/undefined"),
+            Version::V1 => f.write_str("v1"),
+        }
+    }
+}
+
 impl Share {
     /// Construct new `Share` from QR data.
     ///
     /// QR data is provided as decoded QR code in `Vec<u8>` format without QR
     /// header and padding. QR is expected to represent a json String.
+    ///
+    /// Default [`ParseOptions`] are used, in particular share content longer
+    /// than [`DEFAULT_MAX_CONTENT_LENGTH`] is rejected.
     pub fn new(share_qr_data: Vec<u8>) -> Result<Self, BananaError> {
-        // transforming into String
-        let share_string =
-            String::from_utf8(share_qr_data).map_err(|_| BananaError::NotShareString)?;
+        Self::new_with_options(share_qr_data, &ParseOptions::default())
+    }
+
+    /// Construct new `Share` from QR data, in strict mode, see
+    /// [`ParseOptions::strict`].
+    pub fn new_strict(share_qr_data: Vec<u8>) -> Result<Self, BananaError> {
+        Self::new_with_options(
+            share_qr_data,
+            &ParseOptions {
+                strict: true,
+                ..ParseOptions::default()
+            },
+        )
+    }
+
+    /// Construct new `Share` from QR data, with custom [`ParseOptions`].
+    pub fn new_with_options(
+        share_qr_data: Vec<u8>,
+        options: &ParseOptions,
+    ) -> Result<Self, BananaError> {
+        let result = Self::parse(share_qr_data, options);
+        #[cfg(feature = "tracing")]
+        match &result {
+            Ok(share) => tracing::debug!(
+                version = ?share.version,
+                bits = share.bits,
+                id = share.id,
+                content_length = share.content.len(),
+                "share parsed"
+            ),
+            Err(e) => tracing::debug!(error_code = e.code(), "share rejected"),
+        }
+        result
+    }
+
+    /// Parse `Share` from QR data.
+    fn parse(share_qr_data: Vec<u8>, options: &ParseOptions) -> Result<Self, BananaError> {
+        // transforming into String, QR data is zeroized once parsed
+        let share_string = Zeroizing::new(String::from_utf8(share_qr_data).map_err(|e| {
+            let valid_up_to = e.utf8_error().valid_up_to();
+            e.into_bytes().zeroize();
+            BananaError::NotShareString(valid_up_to)
+        })?);
 
         // parsing the string with json
-        let share_parsed: ShareJson =
-            serde_json::from_str(&share_string).map_err(|_| BananaError::JsonParsing)?;
+        let share_parsed: ShareJson = if options.strict {
+            if !(share_string.starts_with('{') && share_string.ends_with('}')) {
+                return Err(BananaError::JsonParsing(String::from(
+                    "unexpected data around json object",
+                )));
+            }
+            serde_json::from_str::<StrictShareJson>(&share_string)
+                .map_err(|e| BananaError::JsonParsing(e.to_string()))?
+                .into()
+        } else {
+            serde_json::from_str(trim_payload(&share_string))
+                .map_err(|e| BananaError::JsonParsing(e.to_string()))?
+        };
 
         // determine protocol version
-        let version = match share_parsed.v {
-            None => Version::Undefined,
-            Some(1) => Version::V1,
-            Some(a) => return Err(BananaError::VersionNotSupported(a)),
-        };
+        let version = Version::from_number(share_parsed.v)?;
+        let spec = version.spec();
+
+        // nonce is decoded right away, so that damaged nonce is detected
+        // with the very first share scanned
+        let nonce =
+            decode_base64(share_parsed.n.as_bytes()).map_err(|_| BananaError::NonceNotBase64)?;
+        if nonce.len() != spec.nonce_length {
+            return Err(BananaError::NonceWrongLength(nonce.len()));
+        }
 
         // process the share data
         let share_chars: Vec<char> = share_parsed.d.chars().collect();
@@ -101,126 +584,972 @@ impl Share {
             None => return Err(BananaError::EmptyShare),
         };
 
+        // length of identificator piece in `u8` units that should be cut from
+        // the beginning of the share_body
+        let id_length = id_length(bits);
+
         // remaining share data is the share body;
-        // it is processed depending on the version;
-        let share_body = match version {
-            // content is hex for version `Undefined`
-            Version::Undefined => hex::decode(String::from_iter(&share_chars[1..]))
-                .map_err(|_| BananaError::UndefinedBodyNotHex)?,
-
-            // content is base64 for version `V1`
-            Version::V1 => base64::decode(String::from_iter(&share_chars[1..]).into_bytes())
-                .map_err(|_| BananaError::BodyNotBase64)?,
-        };
+        // it is encoded depending on the version, and oversized body is
+        // rejected before decoding
+        let body = String::from_iter(&share_chars[1..]);
+        let len = spec.decoded_body_length(&body).saturating_sub(id_length);
+        if len > options.max_content_length {
+            return Err(BananaError::ShareTooLarge {
+                len,
+                max: options.max_content_length,
+            });
+        }
+        // decoded share body is zeroized on drop, also on error
+        let share_body = Zeroizing::new(spec.decode_body(&body)?);
 
         // maximum possible number of shares, `u32`;
-        // bits never exceed 20;
-        // `2^bits` with `bits = 20` or below always fits in `u32` limits
-        let max = 2u32.pow(bits) - 1;
+        // bits never exceed 20, and `2^bits` always fits in `u32` limits,
+        // this is checked anyways in case the range gets widened
+        let max = match 2u32.checked_pow(bits) {
+            Some(a) => a - 1,
+            None => return Err(BananaError::BitsOutOfRange(bits)),
+        };
 
-        // length of identificator piece in `u8` units that should be cut from
-        // the beginning of the share_body;
-        // could not exceed `4`; in given limits, does not exceed `3`;
-        // starting zeroes are removed in length calculation
-        let id_length = max.to_be_bytes().iter().skip_while(|x| x == &&0).count();
+        // set of one share is not split at all, and the set could not need
+        // more shares than could possibly exist
+        if share_parsed.r < 2 {
+            return Err(BananaError::RequiredSharesTooSmall(share_parsed.r));
+        }
+        if share_parsed.r > max as usize {
+            return Err(BananaError::RequiredSharesExceedsField {
+                required: share_parsed.r,
+                max,
+            });
+        }
+
+        // share content is checked again, before it gets copied anywhere
+        let len = share_body.len().saturating_sub(id_length);
+        if len > options.max_content_length {
+            return Err(BananaError::ShareTooLarge {
+                len,
+                max: options.max_content_length,
+            });
+        }
 
         // identifier piece (short `Vec<u8>`) and share content (`Vec<u8>`) separated
-        let (identifier_piece, content) = match share_body.get(..id_length) {
+        let (identifier_piece, mut content) = match share_body.get(..id_length) {
             Some(a) => (a.to_vec(), share_body[id_length..].to_vec()),
             None => return Err(BananaError::ShareTooShort),
         };
 
-        // current share id, `u32`
-        let id = u32::from_be_bytes(
-            [
-                max.to_be_bytes()[..4 - id_length].to_vec(),
-                identifier_piece,
-            ]
-            .concat()
-            .try_into()
-            .expect("fixed length of 4"),
-        );
+        // current share id, `u32`, from big endian identifier piece of at
+        // most 4 bytes
+        let id = identifier_piece
+            .iter()
+            .fold(0u32, |id, byte| (id << 8) | *byte as u32);
+
+        // share numbering starts from 1, zero id would break the combining
+        if id == 0 || id > max {
+            content.zeroize();
+            return Err(BananaError::ShareIdInvalid(id));
+        }
 
         Ok(Share {
             version,
             title: share_parsed.t,
             required_shares: share_parsed.r,
-            nonce: share_parsed.n,
+            nonce,
             bits,
             id,
             content,
         })
     }
 
+    /// Construct new `Share` from share json string.
+    pub fn from_json_str(share_json: &str) -> Result<Self, BananaError> {
+        Self::new(share_json.as_bytes().to_vec())
+    }
+
+    /// Construct new `Share` from QR data in hex format.
+    ///
+    /// Invalid hex results in [`BananaError::PayloadNotHex`].
+    pub fn from_hex_payload(payload_hex: &str) -> Result<Self, BananaError> {
+        Self::new(hex::decode(payload_hex.trim()).map_err(|_| BananaError::PayloadNotHex)?)
+    }
+
+    /// Construct new `Share` from a deep link or a data uri carrying the
+    /// share json.
+    ///
+    /// Accepted formats are:
+    ///
+    /// - `banana://share?d=<json>`, with percent-encoded share json in the
+    ///   `d` query parameter. Parameter `d` must be present exactly once,
+    ///   other parameters and fragment are ignored.
+    /// - `data:application/json,<json>`, with percent-encoded share json.
+    /// - `data:application/json;base64,<json>`, with base64 share json,
+    ///   standard or URL-safe alphabet, padding optional. Base64 data could
+    ///   itself be percent-encoded.
+    ///
+    /// Schemes and the media type are case-insensitive, and media type could
+    /// have parameters, e.g. `data:application/json;charset=utf-8,<json>`.
+    /// Percent-decoding follows RFC 3986, i.e. `+` is not decoded as a
+    /// space.
+    ///
+    /// Uri of any other scheme results in
+    /// [`BananaError::UriSchemeUnsupported`], and an uri that could not be
+    /// processed results in [`BananaError::UriMalformed`]. Extracted json is
+    /// then processed as in [`Share::new`].
+    pub fn from_uri(uri: &str) -> Result<Self, BananaError> {
+        Self::new(uri_payload(trim_payload(uri))?)
+    }
+
     /// Share title.
-    pub fn title(&self) -> String {
-        self.title.to_owned()
+    pub fn title(&self) -> &str {
+        &self.title
     }
-}
 
-/// Shares collector.
-///
-/// Shares could be added only one by one.
-#[derive(Debug)]
-pub enum ShareCollection {
-    /// No shares: freshly initiated or emptied.
-    Empty,
+    /// Share id.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
 
-    /// A set of compatible shares, with fewer shares than the required number.
-    InProgress(SetInProgress),
+    /// Required number of shares.
+    pub fn required_shares(&self) -> usize {
+        self.required_shares
+    }
 
-    /// Combined shares data. Could be processed to get the secret.
-    Ready(SetCombined),
-}
+    /// Bits value.
+    pub fn bits(&self) -> u32 {
+        self.bits
+    }
 
-impl ShareCollection {
-    /// Initiate new share collecting.
-    pub fn new() -> Self {
-        Self::Empty
+    /// Protocol version.
+    pub fn version(&self) -> Version {
+        self.version
     }
 
-    /// Re-start the share collecting.
-    pub fn clear(&mut self) {
-        *self = Self::Empty;
+    /// Decoded nonce.
+    pub fn nonce(&self) -> &[u8] {
+        &self.nonce
     }
 
-    /// Add new share to existing collector.
+    /// Share content length, in bytes.
+    pub fn content_len(&self) -> usize {
+        self.content.len()
+    }
+
+    /// New version `V1` share, for generated shares.
+    pub(crate) fn generated(
+        title: &str,
+        required_shares: usize,
+        nonce: &[u8],
+        id: u32,
+        content: Vec<u8>,
+    ) -> Self {
+        Share {
+            version: Version::V1,
+            title: title.to_owned(),
+            required_shares,
+            nonce: nonce.to_vec(),
+            bits: V1_BITS,
+            id,
+            content,
+        }
+    }
+
+    /// Share of the same set, with given id and content, for fuzzing.
+    #[cfg(feature = "arbitrary")]
+    pub(crate) fn companion(&self, id: u32, content: Vec<u8>) -> Self {
+        let mut share = self.clone();
+        share.id = id;
+        share.content.zeroize();
+        share.content = content;
+        share
+    }
+
+    /// Share json, as in share QR code, in banana split format.
     ///
-    /// If after adding new share the required share number is achieved, shares
-    /// get combined.
-    pub fn add_share(&mut self, share: Share) -> Result<(), BananaError> {
-        // add share
-        match self {
-            Self::Empty => {
-                *self = Self::InProgress(SetInProgress::init(share));
-            }
-            Self::InProgress(in_progress) => {
-                in_progress.add_share(share)?;
+    /// Bits prefix, share id and body encoding are restored according to the
+    /// share version.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.share_json()).expect("share json is always serializable")
+    }
+
+    /// Share QR code payload, utf8 bytes of [`Share::to_json`].
+    ///
+    /// Inverse of [`Share::new`].
+    pub fn to_qr_payload(&self) -> Vec<u8> {
+        self.to_json().into_bytes()
+    }
+
+    /// Canonical share json, for comparison and deduplication.
+    ///
+    /// Keys are sorted, there is no whitespace, body and nonce are encoded in
+    /// standard padded format. Payloads differing only cosmetically, for
+    /// example in key order, result in identical canonical payloads.
+    ///
+    /// Canonical payload is itself a valid share QR payload.
+    pub fn canonical_payload(&self) -> Vec<u8> {
+        // `serde_json::Map` keeps the keys sorted
+        let value =
+            serde_json::to_value(self.share_json()).expect("share json is always serializable");
+        serde_json::to_vec(&value).expect("json value is always serializable")
+    }
+
+    /// Raw share json data.
+    fn share_json(&self) -> ShareJson {
+        let id_bytes = self.id.to_be_bytes();
+        let share_body = [&id_bytes[4 - id_length(self.bits)..], &self.content[..]].concat();
+
+        // first share char is bits info in radix36 format, followed by the
+        // share body encoded depending on the version
+        let spec = self.version.spec();
+        let mut d = String::new();
+        d.push(char::from_digit(self.bits, 36).expect("bits are within BIT_RANGE"));
+        d.push_str(&spec.encode_body(&share_body));
+
+        ShareJson {
+            v: spec.number,
+            t: self.title.to_owned(),
+            r: self.required_shares,
+            d,
+            n: base64::encode(&self.nonce),
+        }
+    }
+
+    /// Secret kind hinted in the share title, if any.
+    ///
+    /// Best-effort guess from keywords in the title, e.g. "BTC seed" hints at
+    /// [`SecretKind::Mnemonic`], and "SSH key" at [`SecretKind::Text`].
+    /// Never authoritative, intended only to pre-select the display format.
+    /// If the title has no known keywords, or keywords for different kinds,
+    /// `None` is returned.
+    pub fn inferred_secret_kind(&self) -> Option<SecretKind> {
+        let title = self.title.to_lowercase();
+        let mut inferred = None;
+        for word in title.split(|c: char| !c.is_alphanumeric()) {
+            let kind = match word {
+                "bip39" | "mnemonic" | "phrase" | "seed" | "seedphrase" => SecretKind::Mnemonic,
+                "hex" => SecretKind::Hex,
+                "gpg" | "password" | "pem" | "pgp" | "ssh" => SecretKind::Text,
+                _ => continue,
+            };
+            match inferred {
+                None => inferred = Some(kind),
+                Some(a) if a == kind => {}
+                Some(_) => return None,
             }
-            Self::Ready(_) => {}
         }
+        inferred
+    }
 
-        // combine if have enough shares
-        if let Self::InProgress(in_progress) = self {
-            if in_progress.id_set.len() >= in_progress.required_shares {
-                let combined = in_progress.combine()?;
-                *self = Self::Ready(combined);
+    /// Compare two shares field by field.
+    ///
+    /// Diagnostic helper, for example to check if two physical copies of the
+    /// same share are identical, and locate the divergence if not.
+    pub fn diff(&self, other: &Share) -> ShareDiff {
+        let content_offset = match self
+            .content
+            .iter()
+            .zip(other.content.iter())
+            .position(|(a, b)| a != b)
+        {
+            Some(a) => Some(a),
+            None => {
+                if self.content.len() == other.content.len() {
+                    None
+                } else {
+                    Some(self.content.len().min(other.content.len()))
+                }
             }
+        };
+        ShareDiff {
+            version: self.version != other.version,
+            title: self.title != other.title,
+            required_shares: self.required_shares != other.required_shares,
+            nonce: self.nonce != other.nonce,
+            bits: self.bits != other.bits,
+            id: self.id != other.id,
+            content_offset,
         }
+    }
 
-        Ok(())
+    /// Non-fatal irregularities in the share.
+    ///
+    /// Protocol permits any bits value in `BIT_RANGE`, however, version `V1`
+    /// shares are known to have bits value `8`. Any other value results in
+    /// [`BananaWarning::UnusualBitsForVersion`], as the bits prefix could be
+    /// damaged.
+    pub fn warnings(&self) -> Vec<BananaWarning> {
+        let mut warnings = Vec::new();
+        if matches!(self.version.spec().usual_bits, Some(bits) if bits != self.bits) {
+            warnings.push(BananaWarning::UnusualBitsForVersion {
+                version: self.version,
+                bits: self.bits,
+            })
+        }
+        warnings
     }
 }
 
-impl Default for ShareCollection {
-    fn default() -> Self {
-        Self::new()
-    }
+/// Kind of the secret, as hinted in share title, from
+/// [`Share::inferred_secret_kind`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum SecretKind {
+    /// Mnemonic seed phrase.
+    Mnemonic,
+
+    /// Hexadecimal string, e.g. a raw private key.
+    Hex,
+
+    /// Arbitrary text, e.g. a password or an SSH key.
+    Text,
 }
 
-/// Incomplete set of compatible shares.
-///
-/// A share could be added to existing set only if the share and the set have
+/// Share json bytes from the deep link or the data uri.
+fn uri_payload(uri: &str) -> Result<Vec<u8>, BananaError> {
+    if let Some(query) = strip_prefix_ignore_case(uri, "banana://share?") {
+        let query = query.split('#').next().unwrap_or_default();
+        let mut data = None;
+        for parameter in query.split('&') {
+            if let Some(value) = parameter.strip_prefix("d=") {
+                if data.replace(value).is_some() {
+                    return Err(BananaError::UriMalformed {
+                        reason: "repeated share data parameter",
+                    });
+                }
+            }
+        }
+        match data {
+            Some(value) => percent_decode(value),
+            None => Err(BananaError::UriMalformed {
+                reason: "no share data parameter",
+            }),
+        }
+    } else if strip_prefix_ignore_case(uri, "banana:").is_some() {
+        Err(BananaError::UriMalformed {
+            reason: "banana link is not a share link",
+        })
+    } else if let Some(content) = strip_prefix_ignore_case(uri, "data:") {
+        let (header, data) = content.split_once(',').ok_or(BananaError::UriMalformed {
+            reason: "no data separator",
+        })?;
+        let mut header_parts = header.split(';');
+        if !header_parts
+            .next()
+            .unwrap_or_default()
+            .eq_ignore_ascii_case("application/json")
+        {
+            return Err(BananaError::UriMalformed {
+                reason: "media type is not application/json",
+            });
+        }
+        let data = percent_decode(data)?;
+        if header_parts.any(|part| part.eq_ignore_ascii_case("base64")) {
+            decode_base64(data).map_err(|_| BananaError::UriMalformed {
+                reason: "data is not in base64 format",
+            })
+        } else {
+            Ok(data)
+        }
+    } else {
+        Err(BananaError::UriSchemeUnsupported)
+    }
+}
+
+/// String remainder after the ASCII prefix, compared case-insensitively.
+fn strip_prefix_ignore_case<'a>(string: &'a str, prefix: &str) -> Option<&'a str> {
+    match string.get(..prefix.len()) {
+        Some(start) if start.eq_ignore_ascii_case(prefix) => Some(&string[prefix.len()..]),
+        _ => None,
+    }
+}
+
+/// Percent-decode uri component, as in RFC 3986.
+fn percent_decode(component: &str) -> Result<Vec<u8>, BananaError> {
+    let mut decoded = Vec::with_capacity(component.len());
+    let mut bytes = component.bytes();
+    while let Some(byte) = bytes.next() {
+        if byte == b'%' {
+            let escape = [
+                bytes.next().unwrap_or_default(),
+                bytes.next().unwrap_or_default(),
+            ];
+            let mut value = [0u8];
+            hex::decode_to_slice(escape, &mut value).map_err(|_| BananaError::UriMalformed {
+                reason: "invalid percent-encoding",
+            })?;
+            decoded.push(value[0]);
+        } else {
+            decoded.push(byte);
+        }
+    }
+    Ok(decoded)
+}
+
+/// QR payload string without the noise added by some scanners: UTF-8 BOM,
+/// ASCII whitespace and NUL characters on the sides.
+fn trim_payload(payload: &str) -> &str {
+    let payload = payload.strip_prefix('\u{feff}').unwrap_or(payload);
+    payload.trim_matches(|c: char| c.is_ascii_whitespace() || c == '\0')
+}
+
+/// Base64 engines for share data decoding, in order of trying.
+///
+/// Banana split itself uses standard padded base64. Some third-party tools
+/// re-encode the share json with padding omitted, or with URL-safe alphabet.
+const BASE64_ENGINES: [FastPortable; 3] = [
+    FastPortable::from(&alphabet::STANDARD, fast_portable::PAD),
+    FastPortable::from(
+        &alphabet::STANDARD,
+        fast_portable::PAD.with_decode_padding_mode(DecodePaddingMode::Indifferent),
+    ),
+    FastPortable::from(
+        &alphabet::URL_SAFE,
+        fast_portable::PAD.with_decode_padding_mode(DecodePaddingMode::Indifferent),
+    ),
+];
+
+/// Decode base64 share data, tolerating omitted padding and URL-safe
+/// alphabet.
+fn decode_base64<T: AsRef<[u8]>>(input: T) -> Result<Vec<u8>, base64::DecodeError> {
+    let mut result = Err(base64::DecodeError::InvalidLength);
+    for engine in BASE64_ENGINES.iter() {
+        result = base64::decode_engine(input.as_ref(), engine);
+        if result.is_ok() {
+            break;
+        }
+    }
+    result
+}
+
+/// Length of the share id piece in share body, in `u8` units.
+///
+/// Could not exceed `4`; for bits in `BIT_RANGE`, does not exceed `3`.
+/// Starting zeroes are removed in length calculation.
+fn id_length(bits: u32) -> usize {
+    let max = 2u32.checked_pow(bits).map_or(u32::MAX, |a| a - 1);
+    max.to_be_bytes().iter().skip_while(|x| x == &&0).count()
+}
+
+/// Basic information on a QR payload recognized as a banana split share.
+#[derive(Debug, Eq, PartialEq)]
+pub struct DetectedShare {
+    /// Protocol version.
+    pub version: Version,
+
+    /// Share set title.
+    pub title: String,
+
+    /// Required number of shares.
+    pub required_shares: usize,
+}
+
+/// Check if the QR payload looks like a banana split share.
+///
+/// Payload is recognized if it is a json with all the expected banana split
+/// keys and supported version. Share data itself is not processed, so
+/// successful detection does not guarantee that [`Share::new`] succeeds.
+///
+/// Intended for general-purpose scanners to dispatch the banana split shares
+/// without getting parse errors for every other QR code.
+pub fn detect(bytes: &[u8]) -> Option<DetectedShare> {
+    let share_string = core::str::from_utf8(bytes).ok()?;
+    let share_parsed: ShareJson = serde_json::from_str(trim_payload(share_string)).ok()?;
+    let version = Version::from_number(share_parsed.v).ok()?;
+    Some(DetectedShare {
+        version,
+        title: share_parsed.t,
+        required_shares: share_parsed.r,
+    })
+}
+
+/// Number of shares for each banana split protocol version encountered in
+/// `shares`.
+///
+/// Helps to audit a share inventory, for example before a migration to a
+/// newer protocol version.
+pub fn version_histogram(shares: &[Share]) -> BTreeMap<Version, usize> {
+    let mut histogram = BTreeMap::new();
+    for share in shares.iter() {
+        *histogram.entry(share.version).or_insert(0) += 1;
+    }
+    histogram
+}
+
+/// Recover the secret from share QR payloads and user-provided passphrase.
+///
+/// All-in-one function for callers having all the payloads at once. Payloads
+/// are processed in order, until the required number of shares is reached;
+/// the remaining payloads are not processed. If there are not enough shares,
+/// [`BananaError::InsufficientShares`] is returned, or
+/// [`BananaError::NoShares`] if there are no payloads at all.
+pub fn recover(payloads: &[Vec<u8>], passphrase: &str) -> Result<String, BananaError> {
+    SetCombined::from_share_iter(
+        payloads.iter().map(|payload| Share::new(payload.to_vec())),
+        true,
+    )?
+    .recover_with_passphrase(passphrase)
+}
+
+/// Combine shares collected separately, for example by different
+/// custodians, into a single set.
+///
+/// All collected shares must belong to the same set, i.e. must be compatible
+/// as in [`SetInProgress`]. Same share appearing in several collections is
+/// used once. Incompatible shares result in
+/// [`BananaError::IncompatibleCollections`], with the cause as the reason:
+/// [`BananaError::ShareAlreadyInSet`] for different shares with the same id,
+/// or the error of [`ShareCollection::add_share`], such as
+/// [`BananaError::ShareTitleDifferent`]. Shares of the same set that could
+/// not be added for other reasons are rejected with the error as is. Empty
+/// collections are ignored.
+///
+/// If all the shares together are still fewer than required,
+/// [`BananaError::ThresholdUnreachable`] is returned.
+pub fn combine_collections(collections: Vec<ShareCollection>) -> Result<SetCombined, BananaError> {
+    let mut merged: Option<SetInProgress> = None;
+    for collection in collections.into_iter() {
+        let shares = match collection {
+            ShareCollection::Empty => continue,
+            ShareCollection::InProgress(in_progress) => in_progress.view().shares(),
+            ShareCollection::Ready(combined) => combined.view().shares(),
+        };
+        for share in shares.into_iter() {
+            match merged {
+                None => merged = Some(SetInProgress::init(share)),
+                Some(ref mut set) => match set.id_set.iter().position(|x| *x == share.id) {
+                    Some(a) => {
+                        // same share known from elsewhere is skipped,
+                        // the compatibility is checked anyways
+                        if share.diff(&set.view().share(a)).is_identical() {
+                            continue;
+                        }
+                        return Err(BananaError::IncompatibleCollections {
+                            reason: Box::new(BananaError::ShareAlreadyInSet),
+                        });
+                    }
+                    None => set.add_share(share).map_err(|e| match e.kind() {
+                        ErrorKind::IncompatibleShare => BananaError::IncompatibleCollections {
+                            reason: Box::new(e),
+                        },
+                        _ => e,
+                    })?,
+                },
+            }
+        }
+    }
+    match merged {
+        None => Err(BananaError::NoShares),
+        Some(set) => {
+            set.check_threshold()?;
+            set.combine()
+        }
+    }
+}
+
+/// Shares collector.
+///
+/// Shares could be added only one by one.
+///
+/// Collection clone is independent from the original, for example to keep
+/// earlier collection states for undo.
+#[derive(Clone, Debug)]
+pub enum ShareCollection {
+    /// No shares: freshly initiated or emptied.
+    Empty,
+
+    /// A set of compatible shares, with fewer shares than the required number.
+    InProgress(SetInProgress),
+
+    /// Combined shares data. Could be processed to get the secret.
+    Ready(SetCombined),
+}
+
+impl ShareCollection {
+    /// Initiate new share collecting.
+    pub fn new() -> Self {
+        Self::Empty
+    }
+
+    /// Re-start the share collecting.
+    pub fn clear(&mut self) {
+        *self = Self::Empty;
+    }
+
+    /// New collector with all the shares added.
+    ///
+    /// Shares are added one by one, the first rejected share results in
+    /// [`BananaError::ShareRejected`] with the share position.
+    pub fn from_shares(shares: impl IntoIterator<Item = Share>) -> Result<Self, BananaError> {
+        let mut share_collection = Self::new();
+        share_collection.try_extend(shares)?;
+        Ok(share_collection)
+    }
+
+    /// Add all the shares to existing collector.
+    ///
+    /// Shares are added one by one, the first rejected share results in
+    /// [`BananaError::ShareRejected`] with the share position in `shares`.
+    /// Shares before it remain added, the ones after it are not processed.
+    pub fn try_extend(
+        &mut self,
+        shares: impl IntoIterator<Item = Share>,
+    ) -> Result<(), BananaError> {
+        for (index, share) in shares.into_iter().enumerate() {
+            if let Err(e) = self.add_share(share) {
+                return Err(BananaError::ShareRejected {
+                    index,
+                    reason: Box::new(e),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the collected shares are enough to recover the secret.
+    ///
+    /// Compatible shares added after combining are not retained, but are
+    /// counted as [`Sufficiency::Redundant`], once per share id.
+    pub fn sufficiency(&self) -> Sufficiency {
+        let (have, need) = match self {
+            Self::Empty => return Sufficiency::NoShares,
+            Self::InProgress(in_progress) => {
+                (in_progress.id_set.len(), in_progress.required_shares)
+            }
+            Self::Ready(combined) => (
+                combined.id_set.len() + combined.extra_ids.len(),
+                combined.required_shares,
+            ),
+        };
+        match have.checked_sub(need) {
+            None => Sufficiency::Insufficient { have, need },
+            Some(0) => Sufficiency::Exact,
+            Some(extra) => Sufficiency::Redundant { extra },
+        }
+    }
+
+    /// Add new share to existing collector.
+    ///
+    /// If after adding new share the required share number is achieved, shares
+    /// get combined.
+    ///
+    /// Shares added to the collector that is already `Ready` are not used,
+    /// but are still checked to be compatible with the combined set, and
+    /// could result in same errors as for [`SetInProgress`].
+    pub fn add_share(&mut self, share: Share) -> Result<AddOutcome, BananaError> {
+        self.add_share_within_memory(share, None, false)
+    }
+
+    /// Add new share to existing collector, allowing content identical to
+    /// that of a share with different id already in collector.
+    ///
+    /// [`ShareCollection::add_share`] rejects such shares with
+    /// [`BananaError::ShareContentDuplicate`], as likely damaged. Identical
+    /// content is still possible, although unlikely, in legitimate shares of
+    /// very short secrets. All other checks are same as in
+    /// [`ShareCollection::add_share`].
+    pub fn add_share_lenient(&mut self, share: Share) -> Result<AddOutcome, BananaError> {
+        self.add_share_within_memory(share, None, true)
+    }
+
+    /// Ids of the collected shares, in order of adding.
+    ///
+    /// For `Ready` collector these are the ids of the combined shares.
+    pub fn ids(&self) -> &[u32] {
+        match self {
+            Self::Empty => &[],
+            Self::InProgress(in_progress) => in_progress.ids(),
+            Self::Ready(combined) => combined.ids(),
+        }
+    }
+
+    /// Combine only the collected shares with given ids, same as
+    /// [`SetInProgress::combine_with`].
+    ///
+    /// For `Ready` collector any of the combined shares could be chosen.
+    /// The collector itself is not changed.
+    pub fn combine_subset(&self, ids: &[u32]) -> Result<SetCombined, BananaError> {
+        match self {
+            Self::Empty => Err(BananaError::NoShares),
+            Self::InProgress(in_progress) => in_progress.combine_with(ids),
+            Self::Ready(combined) => combined.view().combine_with(ids),
+        }
+    }
+
+    /// Check all the reasons why the [`Share`] could not be added to the
+    /// collector, for diagnostics.
+    ///
+    /// `None` for `Empty` collector, any share could be added to it.
+    pub fn mismatch(&self, share: &Share) -> Option<ShareMismatch> {
+        match self {
+            Self::Empty => None,
+            Self::InProgress(in_progress) => Some(in_progress.mismatch(share)),
+            Self::Ready(combined) => Some(combined.view().mismatch(share)),
+        }
+    }
+
+    /// Share set title, `None` for `Empty` collector.
+    pub fn title(&self) -> Option<&str> {
+        match self {
+            Self::Empty => None,
+            Self::InProgress(in_progress) => Some(&in_progress.title),
+            Self::Ready(combined) => Some(&combined.title),
+        }
+    }
+
+    /// Current number of shares in collector, zero for `Empty` collector.
+    pub fn shares_now(&self) -> usize {
+        self.ids().len()
+    }
+
+    /// Required number of shares, `None` for `Empty` collector.
+    pub fn shares_required(&self) -> Option<usize> {
+        match self {
+            Self::Empty => None,
+            Self::InProgress(in_progress) => Some(in_progress.required_shares),
+            Self::Ready(combined) => Some(combined.required_shares),
+        }
+    }
+
+    /// Collector has combined the shares and the secret could be recovered.
+    pub fn is_ready(&self) -> bool {
+        matches!(self, Self::Ready(_))
+    }
+
+    /// Set in progress, if the collector is `InProgress`.
+    pub fn in_progress(&self) -> Option<&SetInProgress> {
+        match self {
+            Self::InProgress(in_progress) => Some(in_progress),
+            _ => None,
+        }
+    }
+
+    /// Combined set, if the collector is `Ready`.
+    pub fn ready(&self) -> Option<&SetCombined> {
+        match self {
+            Self::Ready(combined) => Some(combined),
+            _ => None,
+        }
+    }
+
+    /// Take the combined set out of `Ready` collector.
+    ///
+    /// Collector that is not `Ready` is returned back unchanged as an error.
+    #[allow(clippy::result_large_err)]
+    pub fn take_ready(self) -> Result<SetCombined, Self> {
+        match self {
+            Self::Ready(combined) => Ok(combined),
+            _ => Err(self),
+        }
+    }
+
+    /// Remove the share with given id from the collector.
+    ///
+    /// Removing a share from `Ready` collector brings it back to
+    /// `InProgress`. Removing the last share makes the collector `Empty`.
+    /// Share id not in collector results in [`BananaError::ShareIdNotInSet`].
+    pub fn remove_share(&mut self, id: u32) -> Result<(), BananaError> {
+        match self {
+            Self::Empty => return Err(BananaError::ShareIdNotInSet(id)),
+            Self::InProgress(in_progress) => in_progress.remove_share(id)?,
+            Self::Ready(combined) => {
+                // combined set is taken apart only if the share is there
+                if !combined.id_set.contains(&id) {
+                    return Err(BananaError::ShareIdNotInSet(id));
+                }
+                let mut in_progress = combined.take_in_progress();
+                in_progress.remove_share(id)?;
+                *self = Self::InProgress(in_progress);
+            }
+        }
+        if let Self::InProgress(in_progress) = self {
+            if in_progress.id_set.is_empty() {
+                *self = Self::Empty;
+            }
+        }
+        Ok(())
+    }
+
+    /// Add new share to existing collector, with memory budget for combining.
+    ///
+    /// Combining uses pre-calculated tables of logarithms and exponents in
+    /// `GF(2^bits)`, `8 * 2^bits` bytes in total, i.e. 8 MiB for `bits = 20`.
+    /// Tables for `bits = 8` are built-in, and always used regardless of the
+    /// budget. If the tables do not fit in `memory_budget` bytes, the field
+    /// arithmetic is done without the tables. This is much slower, roughly
+    /// `bits` times for multiplication and `bits^2` times for division, but
+    /// needs no memory besides the shares themselves.
+    ///
+    /// Recovered data is identical to that of [`ShareCollection::add_share`].
+    pub fn add_share_with_memory_budget(
+        &mut self,
+        share: Share,
+        memory_budget: usize,
+    ) -> Result<AddOutcome, BananaError> {
+        self.add_share_within_memory(share, Some(memory_budget), false)
+    }
+
+    /// Add new share to existing collector, with optional memory budget for
+    /// combining, and optionally allowing duplicate content.
+    fn add_share_within_memory(
+        &mut self,
+        share: Share,
+        memory_budget: Option<usize>,
+        lenient: bool,
+    ) -> Result<AddOutcome, BananaError> {
+        // add share
+        match self {
+            Self::Empty => {
+                *self = Self::InProgress(SetInProgress::init(share));
+            }
+            Self::InProgress(in_progress) => {
+                if lenient {
+                    in_progress.add_share_lenient(share)?;
+                } else {
+                    in_progress.add_share(share)?;
+                }
+            }
+            Self::Ready(combined) => {
+                // checked against the set, and discarded, only id is counted
+                let set = combined.view();
+                if lenient {
+                    set.would_accept_lenient(&share)?;
+                } else {
+                    set.would_accept(&share)?;
+                }
+                if !combined.extra_ids.contains(&share.id) {
+                    combined.extra_ids.push(share.id);
+                }
+                return Ok(AddOutcome::AlreadyReady);
+            }
+        }
+
+        // combine if have enough shares
+        if let Self::InProgress(in_progress) = self {
+            if in_progress.id_set.len() >= in_progress.required_shares {
+                let mut combined = match memory_budget {
+                    Some(a) if tables_memory_bytes(in_progress.bits) > a => {
+                        in_progress.combine_without_tables()?
+                    }
+                    _ => in_progress.combine()?,
+                };
+                // tables, if calculated in combining, go into combined set
+                combined.tables = core::mem::take(&mut in_progress.tables);
+                #[cfg(feature = "tracing")]
+                tracing::info!(
+                    shares = combined.id_set.len(),
+                    required_shares = combined.required_shares,
+                    "collection ready"
+                );
+                *self = Self::Ready(combined);
+            }
+        }
+
+        if let Self::Ready(_) = self {
+            Ok(AddOutcome::Ready)
+        } else {
+            Ok(AddOutcome::InProgress)
+        }
+    }
+}
+
+/// Collector for shares of several different sets, scanned in any order.
+///
+/// Each set is identified by nonce, title, bits value and version. Incoming
+/// share is added to the matching set, or starts a new one.
+#[derive(Debug, Default)]
+pub struct MultiShareCollection {
+    sets: Vec<ShareCollection>,
+}
+
+impl MultiShareCollection {
+    /// Initiate new collecting.
+    pub fn new() -> Self {
+        Self { sets: Vec::new() }
+    }
+
+    /// Add new share to the matching set, or to a new set.
+    ///
+    /// Share matching an existing set is added as in
+    /// [`ShareCollection::add_share`], and could still be rejected, for
+    /// example if the required number of shares is different.
+    pub fn add_share(&mut self, share: Share) -> Result<AddOutcome, BananaError> {
+        let matching = self.sets.iter_mut().find(|set| match set {
+            ShareCollection::Empty => false,
+            ShareCollection::InProgress(in_progress) => in_progress.identified_by(&share),
+            ShareCollection::Ready(combined) => combined.view().identified_by(&share),
+        });
+        match matching {
+            Some(set) => set.add_share(share),
+            None => {
+                let mut set = ShareCollection::new();
+                let outcome = set.add_share(share)?;
+                self.sets.push(set);
+                Ok(outcome)
+            }
+        }
+    }
+
+    /// All sets, in order of the first share scanning.
+    pub fn sets(&self) -> &[ShareCollection] {
+        &self.sets
+    }
+
+    /// Sets that are combined already.
+    pub fn ready(&self) -> impl Iterator<Item = &SetCombined> {
+        self.sets.iter().filter_map(|set| match set {
+            ShareCollection::Ready(combined) => Some(combined),
+            _ => None,
+        })
+    }
+}
+
+/// Result of adding a share to [`ShareCollection`].
+#[derive(Debug, Eq, PartialEq)]
+pub enum AddOutcome {
+    /// Share added, more shares are needed.
+    InProgress,
+
+    /// Share added, and the shares got combined.
+    Ready,
+
+    /// Shares were combined already, compatible share was not used, but is
+    /// counted in [`ShareCollection::sufficiency`].
+    AlreadyReady,
+}
+
+/// Collected shares compared to the required number of shares, from
+/// [`ShareCollection::sufficiency`].
+#[derive(Debug, Eq, PartialEq)]
+pub enum Sufficiency {
+    /// No shares collected.
+    NoShares,
+
+    /// Fewer shares than required.
+    Insufficient {
+        /// Number of collected shares.
+        have: usize,
+
+        /// Required number of shares.
+        need: usize,
+    },
+
+    /// Exactly the required number of shares.
+    Exact,
+
+    /// More shares than required.
+    Redundant {
+        /// Number of extra shares.
+        extra: usize,
+    },
+}
+
+impl Default for ShareCollection {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Incomplete set of compatible shares.
+///
+/// A share could be added to existing set only if the share and the set have
 /// matching:
 ///
 /// - `Version`
@@ -235,217 +1564,1811 @@ impl Default for ShareCollection {
 /// - has same content length as other shares in the set
 ///
 /// Otherwise, adding the share would result in an error.
-#[derive(Debug)]
+///
+/// `Debug` output does not include shares contents and nonce.
+///
+/// Shares contents and nonce are zeroized on drop, in clones as well.
+#[derive(Clone)]
 pub struct SetInProgress {
     version: Version,
     title: String,
     required_shares: usize,
-    nonce: String,
+    nonce: Vec<u8>,
     bits: u32,
     id_set: Vec<u32>,
     content_length: usize,
     content_set: Vec<Vec<u8>>,
+    exhausted: bool,
+    tables: TablesCache,
 }
 
-impl SetInProgress {
-    /// New set from a [`Share`].
-    fn init(share: Share) -> Self {
-        Self {
-            version: share.version,
-            title: share.title,
-            required_shares: share.required_shares,
-            nonce: share.nonce,
-            bits: share.bits,
-            id_set: vec![share.id],
-            content_length: share.content.len(),
-            content_set: vec![share.content],
+impl SetInProgress {
+    /// New set from a [`Share`].
+    ///
+    /// Capacity for the required number of shares is reserved right away.
+    /// Required number of shares comes from share json as is, so the
+    /// reserved capacity is capped by the maximum possible number of shares
+    /// for the share bits.
+    pub(crate) fn init(mut share: Share) -> Self {
+        // `Share` zeroizes its buffers on drop, so these are taken rather than moved
+        let capacity = share.required_shares.min(2usize.pow(share.bits) - 1);
+        let mut id_set = Vec::with_capacity(capacity);
+        id_set.push(share.id);
+        let mut content_set = Vec::with_capacity(capacity);
+        let content_length = share.content.len();
+        content_set.push(core::mem::take(&mut share.content));
+        Self {
+            version: share.version,
+            title: core::mem::take(&mut share.title),
+            required_shares: share.required_shares,
+            nonce: core::mem::take(&mut share.nonce),
+            bits: share.bits,
+            id_set,
+            content_length,
+            content_set,
+            exhausted: false,
+            tables: TablesCache::default(),
+        }
+    }
+
+    /// Capacities of id and content buffers, to check the reservation.
+    #[cfg(test)]
+    pub(crate) fn capacity(&self) -> (usize, usize) {
+        (self.id_set.capacity(), self.content_set.capacity())
+    }
+
+    /// Borrowed view of the set.
+    pub(crate) fn view(&self) -> SetView<'_> {
+        SetView {
+            version: self.version,
+            title: &self.title,
+            required_shares: self.required_shares,
+            nonce: &self.nonce,
+            bits: self.bits,
+            id_set: &self.id_set,
+            content_length: self.content_length,
+            content_set: &self.content_set,
+            tables: &self.tables,
+        }
+    }
+
+    /// Add new [`Share`] to existing set.
+    pub(crate) fn add_share(&mut self, new_share: Share) -> Result<(), BananaError> {
+        self.would_accept(&new_share)?;
+        self.insert(new_share);
+        Ok(())
+    }
+
+    /// Add new [`Share`] to existing set, even if its content is identical
+    /// to that of a share already in set.
+    pub(crate) fn add_share_lenient(&mut self, new_share: Share) -> Result<(), BananaError> {
+        self.would_accept_lenient(&new_share)?;
+        self.insert(new_share);
+        Ok(())
+    }
+
+    /// Add checked [`Share`] to the set.
+    ///
+    /// Share found after the set was declared exhausted revokes the
+    /// declaration.
+    fn insert(&mut self, mut new_share: Share) {
+        self.exhausted = false;
+        self.id_set.push(new_share.id);
+        self.content_set
+            .push(core::mem::take(&mut new_share.content));
+    }
+
+    /// Share is from this set, as identified by nonce, title, bits value and
+    /// version.
+    ///
+    /// Share could still be incompatible with the set.
+    fn identified_by(&self, share: &Share) -> bool {
+        self.view().identified_by(share)
+    }
+
+    /// Check if the [`Share`] could be added to the set, without adding it.
+    ///
+    /// Returns same errors as adding the share would.
+    ///
+    /// Share with content identical to that of a share with different id
+    /// already in set results in [`BananaError::ShareContentDuplicate`].
+    /// Different shares of the same set could have identical content only
+    /// by extreme coincidence, much more likely it is a misprint or a stale
+    /// scan, and combining such shares would fail in decoding.
+    pub fn would_accept(&self, new_share: &Share) -> Result<(), BananaError> {
+        self.view().would_accept(new_share)
+    }
+
+    /// Check if the [`Share`] could be added to the set, allowing content
+    /// identical to that of a share already in set.
+    ///
+    /// Identical content is possible, although unlikely, in legitimate shares
+    /// of very short secrets.
+    pub fn would_accept_lenient(&self, new_share: &Share) -> Result<(), BananaError> {
+        self.view().would_accept_lenient(new_share)
+    }
+
+    /// Check all the reasons why the [`Share`] could not be added to the
+    /// set, for diagnostics.
+    ///
+    /// Share is compatible if and only if
+    /// [`SetInProgress::would_accept`] accepts it.
+    pub fn mismatch(&self, new_share: &Share) -> ShareMismatch {
+        self.view().mismatch(new_share)
+    }
+
+    /// Remove the share with given id from the set, for example if the
+    /// share turns out to be damaged.
+    ///
+    /// Share id not in set results in [`BananaError::ShareIdNotInSet`].
+    pub fn remove_share(&mut self, id: u32) -> Result<(), BananaError> {
+        match self.id_set.iter().position(|x| *x == id) {
+            Some(a) => {
+                let _ = self.id_set.remove(a);
+                let _ = self.content_set.remove(a);
+                Ok(())
+            }
+            None => Err(BananaError::ShareIdNotInSet(id)),
+        }
+    }
+
+    /// Combine `SetInProgress` into [`SetCombined`].
+    ///
+    /// Function must be applied only if the set is checked elsewhere to have at
+    /// least the required number of shares.
+    ///
+    /// Tables of logarithms and exponents are calculated on first combining
+    /// and retained in the set, so that repeated combining does not rebuild
+    /// them.
+    pub(crate) fn combine(&self) -> Result<SetCombined, BananaError> {
+        #[cfg(feature = "tracing")]
+        let _span = self.combine_span().entered();
+        let result = self.combine_raw_bits()?;
+        self.complete(result)
+    }
+
+    /// Span of the combining, timed by the subscriber.
+    #[cfg(feature = "tracing")]
+    fn combine_span(&self) -> tracing::Span {
+        tracing::debug_span!("combine", shares = self.id_set.len(), bits = self.bits)
+    }
+
+    /// Combine `SetInProgress` into [`SetCombined`], without pre-calculated
+    /// tables of logarithms and exponents.
+    ///
+    /// Function must be applied only if the set is checked elsewhere to have at
+    /// least the required number of shares.
+    fn combine_without_tables(&self) -> Result<SetCombined, BananaError> {
+        #[cfg(feature = "tracing")]
+        let _span = self.combine_span().entered();
+        let polynomial = primitive_polynomial(self.bits);
+        let result = self
+            .raw_bits_with(|y| lagrange_without_tables(&self.id_set, y, self.bits, polynomial))?;
+        self.complete(result)
+    }
+
+    /// Combine `SetInProgress` into [`SetCombined`], using caller-provided
+    /// table of primitive polynomials.
+    ///
+    /// This is an escape hatch for shares made by non-standard generators,
+    /// that used primitive polynomials different from the ones in
+    /// <https://github.com/grempe/secrets.js/blob/master/secrets.js#L55>.
+    /// Everywhere else the built-in table is used.
+    ///
+    /// Table `polynomials` is indexed same way as the built-in one, i.e. it
+    /// starts with the polynomial for `bits = 3` (`BIT_RANGE` start). Only the
+    /// polynomial for the set bits value is used, it must be present and
+    /// primitive.
+    ///
+    /// Note that [`ShareCollection`] combines the set automatically with the
+    /// built-in table once the required number of shares is reached. If this
+    /// combining fails, the collection retains the set in progress with all
+    /// the shares, and this method could be applied.
+    pub fn combine_with_polynomials(
+        &self,
+        polynomials: &[u32],
+    ) -> Result<SetCombined, BananaError> {
+        if self.id_set.len() < self.required_shares {
+            return Err(BananaError::ThresholdUnreachable {
+                have: self.id_set.len(),
+                need: self.required_shares,
+            });
+        }
+        let polynomial = match polynomials.get((self.bits - BIT_RANGE.start()) as usize) {
+            Some(a) => *a,
+            None => return Err(BananaError::PolynomialMissing(self.bits)),
+        };
+
+        // calculate logarithms and exponents in `GF(2^self.bits)`;
+        // all logarithms except `log[0]` must get defined, otherwise the
+        // polynomial is not primitive
+        let (logs, exps) = generate_logs_and_exps_with_polynomial(self.bits, polynomial);
+        if logs[0] != LOG_UNDEFINED || logs[1..].contains(&LOG_UNDEFINED) {
+            return Err(BananaError::PolynomialNotPrimitive(polynomial));
+        }
+
+        let result = self.raw_bits_with_tables(&logs, &exps)?;
+        self.complete(result)
+    }
+
+    /// Process reconstructed bit sequence of the set into [`SetCombined`].
+    fn complete(&self, mut result: BitVec<u8, Msb0>) -> Result<SetCombined, BananaError> {
+        // the js code this crate follows calls for cutting all leading false bits
+        // up until the first true, which serves as a padding marker,
+        // cut padding marker as well, and then collect bytes with some padding on the left if necessary;
+        // the padding marker must be present, and the bits remaining after it must form whole bytes,
+        // otherwise the combined data is certainly corrupted, and this is
+        // reported before any expensive passphrase processing
+        let marker = match result.first_one() {
+            Some(a) if (result.len() - a - 1) % 8 == 0 => a,
+            _ => {
+                result.as_raw_mut_slice().zeroize();
+                return Err(BananaError::MalformedPadding);
+            }
+        };
+        let mut stripped: BitVec<u8, Msb0> = BitVec::with_capacity(result.len() - marker - 1);
+        stripped.extend_from_bitslice(&result[marker + 1..]);
+        result.as_raw_mut_slice().zeroize();
+
+        // transform result in its final form, `Vec<u8>`;
+        // encrypted data starts with Poly1305 tag, shorter data could not
+        // be decrypted with any passphrase
+        let mut data = stripped.into_vec();
+        if data.len() < TAG_LENGTH {
+            let len = data.len();
+            data.zeroize();
+            return Err(BananaError::CombinedDataMalformed(len));
+        }
+
+        // now the set is ready
+        Ok(SetCombined {
+            data,
+            nonce: self.nonce.to_owned(),
+            title: self.title.to_owned(),
+            version: self.version,
+            required_shares: self.required_shares,
+            bits: self.bits,
+            id_set: self.id_set.to_owned(),
+            content_set: self.content_set.to_owned(),
+            tables: TablesCache::default(),
+            extra_ids: Vec::new(),
+        })
+    }
+
+    /// Reconstructed bit sequence of the set, before the padding marker is
+    /// processed.
+    ///
+    /// Exposes intermediate combining result, for debugging.
+    pub(crate) fn combine_raw_bits(&self) -> Result<BitVec<u8, Msb0>, BananaError> {
+        let tables = self.tables.get(self.bits);
+        self.raw_bits_with_tables(tables.logs(), tables.exps())
+    }
+
+    /// Tables of logarithms and exponents, as retained in the set.
+    #[cfg(test)]
+    pub(crate) fn tables(&self) -> &FieldTables {
+        self.tables.get(self.bits)
+    }
+
+    /// Reconstructed bit sequence of the set, using given pre-calculated
+    /// logarithms and exponents in `GF(2^self.bits)`.
+    fn raw_bits_with_tables(
+        &self,
+        logs: &[u32],
+        exps: &[u32],
+    ) -> Result<BitVec<u8, Msb0>, BananaError> {
+        self.raw_bits_with(|y| lagrange(&self.id_set, y, logs, exps, self.bits))
+    }
+
+    /// Reconstructed bit sequence of the set, using given Lagrange
+    /// interpolation for each element.
+    ///
+    /// With `rayon` feature the elements of large sets are interpolated in
+    /// parallel.
+    fn raw_bits_with(
+        &self,
+        interpolate: impl Fn(&[u32]) -> Result<u32, BananaError> + Sync,
+    ) -> Result<BitVec<u8, Msb0>, BananaError> {
+        #[cfg(feature = "rayon")]
+        if self.content_length >= PARALLEL_CONTENT_LENGTH {
+            return self.raw_bits_parallel(interpolate);
+        }
+        self.raw_bits_sequential(interpolate)
+    }
+
+    /// Reconstructed bit sequence of the set, with elements interpolated one
+    /// by one.
+    pub(crate) fn raw_bits_sequential(
+        &self,
+        interpolate: impl Fn(&[u32]) -> Result<u32, BananaError>,
+    ) -> Result<BitVec<u8, Msb0>, BananaError> {
+        // process the content set column by column, i.e. the elements
+        // `[share1[i], share2[i] ... shareM[i]]` one `i` at a time,
+        // gathered into a single reusable buffer;
+        // capacity is reserved, so that no copies are left behind on reallocation
+        let mut column: Vec<u32> = Vec::with_capacity(self.id_set.len());
+        let mut result: BitVec<u8, Msb0> =
+            BitVec::with_capacity(self.content_length * self.bits as usize);
+        for i in 0..self.content_length {
+            column.clear();
+            column.extend(self.content_set.iter().map(|content| content[i] as u32));
+
+            // new element that will be processed; is calculated as `u32`, its value is always below `2^self.bits`;
+            let new = match interpolate(&column) {
+                Ok(a) => a,
+                Err(e) => {
+                    result.as_raw_mut_slice().zeroize();
+                    column.zeroize();
+                    return Err(e);
+                }
+            };
+
+            // in js code this crate follows, the bits string representation of new element (i.e. without leading zeroes)
+            // was padded from left with zeroes so that the string length became multiple of `self.bits` number;
+            // since the new element value is always below `2^self.bits`, this procedure effectively means keeping only
+            // `self.bits` amount of bits from the element, most significant first;
+            for k in (0..self.bits).rev() {
+                result.push((new >> k) & 1 == 1);
+            }
+        }
+        column.zeroize();
+
+        Ok(result)
+    }
+
+    /// Reconstructed bit sequence of the set, with elements interpolated in
+    /// parallel.
+    ///
+    /// Result is identical to [`SetInProgress::raw_bits_sequential`].
+    #[cfg(feature = "rayon")]
+    pub(crate) fn raw_bits_parallel(
+        &self,
+        interpolate: impl Fn(&[u32]) -> Result<u32, BananaError> + Sync,
+    ) -> Result<BitVec<u8, Msb0>, BananaError> {
+        use rayon::prelude::*;
+
+        // each element is interpolated independently, by worker with its own
+        // reusable buffer for the content set column; buffers and elements
+        // are zeroized on drop, also on error
+        let mut elements: Zeroizing<Vec<u32>> = Zeroizing::new(vec![0; self.content_length]);
+        elements.par_iter_mut().enumerate().try_for_each_init(
+            || Zeroizing::new(Vec::with_capacity(self.id_set.len())),
+            |column: &mut Zeroizing<Vec<u32>>, (i, element)| {
+                column.clear();
+                column.extend(self.content_set.iter().map(|content| content[i] as u32));
+                *element = interpolate(column)?;
+                Ok(())
+            },
+        )?;
+
+        // bits are assembled in order, same way as in sequential processing
+        let mut result: BitVec<u8, Msb0> =
+            BitVec::with_capacity(self.content_length * self.bits as usize);
+        for new in elements.iter() {
+            for k in (0..self.bits).rev() {
+                result.push((new >> k) & 1 == 1);
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Derive a brand-new share of the set, for example to replace a lost one.
+    ///
+    /// Shamir polynomial consistent with all the shares in set is evaluated
+    /// at `new_id`, there must be at least the required number of shares.
+    /// Neither the passphrase nor the secret is needed. New share has same
+    /// title, nonce, bits and version as the set, and could be used with any
+    /// other shares of the set.
+    ///
+    /// Note that without decrypting the secret there is no check that the
+    /// shares in set are correct. Id already in set results in
+    /// [`BananaError::ShareAlreadyInSet`].
+    ///
+    /// Share content elements are bytes, so for bits above `8` the evaluated
+    /// content could be not representable, resulting in
+    /// [`BananaError::DerivedShareUnrepresentable`].
+    pub fn derive_new_share(&self, new_id: u32) -> Result<Share, BananaError> {
+        self.view().derive_new_share(new_id)
+    }
+
+    /// Combine only the shares with given ids into [`SetCombined`].
+    ///
+    /// This is for choosing the shares manually, for example if some share
+    /// is suspected to be damaged. Ids must be in set, each only once,
+    /// otherwise [`BananaError::ShareIdNotInSet`] or
+    /// [`BananaError::ShareAlreadyInSet`] is returned. Fewer ids than
+    /// required results in [`BananaError::ThresholdUnreachable`].
+    pub fn combine_with(&self, ids: &[u32]) -> Result<SetCombined, BananaError> {
+        self.view().combine_with(ids)
+    }
+
+    /// Cross-check the shares of the set, if there are more shares than
+    /// required.
+    ///
+    /// Subsets of exactly required number of shares are combined and the
+    /// results compared, no passphrase is needed. Up to
+    /// [`MAX_CONSISTENCY_SUBSETS`] subsets are checked, in lexicographic
+    /// order of share positions in set.
+    ///
+    /// If the results differ, the result produced by the most subsets is
+    /// taken as correct, provided it is the only most common one. Shares
+    /// that are not in any of the subsets producing it are reported as
+    /// suspects. With one damaged share in set, this pinpoints the damaged
+    /// share if the set has at least two shares more than required.
+    ///
+    /// Set with no extra shares results in
+    /// [`BananaError::ThresholdUnreachable`], with one extra share needed.
+    pub fn consistency_check(&self) -> Result<ConsistencyReport, BananaError> {
+        self.view().consistency_check()
+    }
+
+    /// Declare that no more shares could be added to the set.
+    ///
+    /// To be called when the user confirms that all available shares are
+    /// already scanned. Results in [`BananaError::ThresholdUnreachable`] if the
+    /// set has fewer shares than required, i.e. the secret could not be
+    /// recovered with these shares.
+    ///
+    /// Declaration is recorded in the set, see
+    /// [`SetInProgress::is_exhausted`].
+    pub fn declare_exhausted(&mut self) -> Result<(), BananaError> {
+        self.exhausted = true;
+        self.check_threshold()
+    }
+
+    /// Set was declared exhausted with
+    /// [`SetInProgress::declare_exhausted`], and no shares were added since.
+    pub fn is_exhausted(&self) -> bool {
+        self.exhausted
+    }
+
+    /// Set has at least the required number of shares, otherwise
+    /// [`BananaError::ThresholdUnreachable`] is returned.
+    pub(crate) fn check_threshold(&self) -> Result<(), BananaError> {
+        self.view().check_threshold()
+    }
+
+    /// Current number of shares in set.
+    pub fn shares_now(&self) -> usize {
+        self.id_set.len()
+    }
+
+    /// Required number of shares.
+    pub fn shares_required(&self) -> usize {
+        self.required_shares
+    }
+
+    /// Ids of the shares in set, in order of adding.
+    pub fn ids(&self) -> &[u32] {
+        &self.id_set
+    }
+
+    /// Share with given id is in set.
+    pub fn contains_id(&self, id: u32) -> bool {
+        self.id_set.contains(&id)
+    }
+
+    /// Number of shares still needed to reach the required number of shares.
+    ///
+    /// Zero if the set somehow has enough shares already.
+    pub fn shares_missing(&self) -> usize {
+        self.required_shares.saturating_sub(self.id_set.len())
+    }
+
+    /// Collecting progress summary, for display.
+    pub fn progress(&self) -> Progress {
+        Progress {
+            collected: self.shares_now(),
+            required: self.shares_required(),
+            missing: self.shares_missing(),
+            title: self.title.to_owned(),
+            bits: self.bits,
+        }
+    }
+
+    /// Expected share ids still missing from the set, no more than needed to
+    /// reach the required number of shares.
+    ///
+    /// `all_expected_ids` are the ids known to exist, for example from the
+    /// backup documentation. Missing ids are returned in the same order.
+    pub fn outstanding_required(&self, all_expected_ids: &[u32]) -> Vec<u32> {
+        let needed = self.shares_missing();
+        let mut outstanding = Vec::with_capacity(needed);
+        for id in all_expected_ids.iter() {
+            if outstanding.len() == needed {
+                break;
+            }
+            if !self.id_set.contains(id) && !outstanding.contains(id) {
+                outstanding.push(*id);
+            }
+        }
+        outstanding
+    }
+
+    /// Ratio of current number of shares to required number of shares.
+    ///
+    /// Value `1.0` means the set has exactly the required number of shares,
+    /// values above `1.0` mean there are extra shares. Never `NaN` or
+    /// infinite, even for nonsensical zero required shares.
+    pub fn redundancy(&self) -> f32 {
+        self.id_set.len() as f32 / self.required_shares.max(1) as f32
+    }
+
+    /// Share set title.
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Bits value of the set shares.
+    pub fn bits(&self) -> u32 {
+        self.bits
+    }
+
+    /// Decoded nonce.
+    pub fn nonce(&self) -> &[u8] {
+        &self.nonce
+    }
+
+    /// Nonce re-encoded in base64 format, as in share json.
+    pub fn nonce_base64(&self) -> String {
+        base64::encode(&self.nonce)
+    }
+
+    /// Export all collected shares as a single encrypted archive.
+    ///
+    /// Share jsons are newline-delimited, as in [`parse_archive`] input, and
+    /// encrypted with the key derived from `archive_passphrase`, unrelated to
+    /// the secret passphrase. Key derivation and encryption are the same as
+    /// for the banana split secret.
+    ///
+    /// Scrypt salt and nonce are taken from `rng`, as in [`split_secret`].
+    /// The crate has no randomness source of its own in `no_std` builds, and
+    /// salt and nonce derived from the archive content instead would make
+    /// identical sets produce identical archives, disclosing that.
+    ///
+    /// Encryption failure results in [`BananaError::ArchiveEncryptionFailed`].
+    ///
+    /// [`parse_archive`]: crate::parse_archive
+    /// [`split_secret`]: crate::split_secret
+    pub fn to_encrypted_archive(
+        &self,
+        archive_passphrase: &str,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Result<Vec<u8>, BananaError> {
+        let mut lines: Vec<Vec<u8>> = self
+            .view()
+            .shares()
+            .iter()
+            .map(Share::to_qr_payload)
+            .collect();
+        let mut plaintext = lines.join(&b'\n');
+        lines.zeroize();
+
+        // salt and nonce, prepended to the encrypted data
+        let mut salt = [0u8; ARCHIVE_SALT_LENGTH];
+        rng.fill_bytes(&mut salt);
+        let mut nonce = [0u8; ARCHIVE_NONCE_LENGTH];
+        rng.fill_bytes(&mut nonce);
+
+        let encrypted = cipher(archive_passphrase.as_bytes(), &salt).and_then(|cipher| {
+            cipher
+                .encrypt(GenericArray::from_slice(&nonce), plaintext.as_ref())
+                .map_err(|_| BananaError::ArchiveEncryptionFailed)
+        });
+        plaintext.zeroize();
+
+        Ok([&salt[..], &nonce[..], &encrypted?[..]].concat())
+    }
+
+    /// Restore the set from an archive made by
+    /// [`SetInProgress::to_encrypted_archive`].
+    ///
+    /// Wrong `archive_passphrase` or damaged archive, truncated one
+    /// included, results in [`BananaError::DecodingFailed`]. Archived shares
+    /// are processed same way as the scanned ones, and must form a compatible
+    /// set.
+    pub fn from_encrypted_archive(
+        archive: &[u8],
+        archive_passphrase: &str,
+    ) -> Result<Self, BananaError> {
+        if archive.len() < ARCHIVE_SALT_LENGTH + ARCHIVE_NONCE_LENGTH {
+            return Err(BananaError::DecodingFailed);
+        }
+        let (salt, rest) = archive.split_at(ARCHIVE_SALT_LENGTH);
+        let (nonce, encrypted) = rest.split_at(ARCHIVE_NONCE_LENGTH);
+        let plaintext = Zeroizing::new(
+            cipher(archive_passphrase.as_bytes(), salt)?
+                .decrypt(GenericArray::from_slice(nonce), encrypted)
+                .map_err(|_| BananaError::DecodingFailed)?,
+        );
+
+        let mut set: Option<Self> = None;
+        for line in plaintext.split(|x| *x == b'\n') {
+            // line copy is zeroized in parsing
+            let share = Share::new(line.to_vec())?;
+            match set {
+                Some(ref mut a) => a.add_share(share)?,
+                None => set = Some(Self::init(share)),
+            }
+        }
+        // splitting always yields at least one line, so this is not reached
+        set.ok_or(BananaError::NoShares)
+    }
+}
+
+/// Length of scrypt salt in encrypted share archive.
+const ARCHIVE_SALT_LENGTH: usize = 32;
+
+/// Length of XSalsa20Poly1305 nonce in encrypted share archive.
+const ARCHIVE_NONCE_LENGTH: usize = 24;
+
+/// Maximum number of share subsets combined in
+/// [`SetInProgress::consistency_check`].
+pub const MAX_CONSISTENCY_SUBSETS: usize = 256;
+
+/// Result of [`SetInProgress::consistency_check`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConsistencyReport {
+    /// Number of share subsets combined.
+    pub subsets_checked: usize,
+
+    /// All subsets combined into the same result.
+    pub consistent: bool,
+
+    /// Ids of the shares suspected to be damaged, empty if the results agree
+    /// or if there is no single most common result.
+    pub suspects: Vec<u32>,
+}
+
+impl ConsistencyReport {
+    /// Id of the single damaged share, if it could be pinpointed.
+    pub fn outlier(&self) -> Option<u32> {
+        match self.suspects[..] {
+            [a] => Some(a),
+            _ => None,
+        }
+    }
+}
+
+/// Borrowed view of the set data, shared by [`SetInProgress`] and
+/// [`SetCombined`], so that the combined set is inspected without copying
+/// the shares contents.
+#[derive(Clone, Copy)]
+pub(crate) struct SetView<'a> {
+    version: Version,
+    title: &'a str,
+    required_shares: usize,
+    nonce: &'a [u8],
+    bits: u32,
+    id_set: &'a [u32],
+    content_length: usize,
+    content_set: &'a [Vec<u8>],
+    tables: &'a TablesCache,
+}
+
+impl SetView<'_> {
+    /// Share is from this set, same as [`SetInProgress::identified_by`].
+    fn identified_by(&self, share: &Share) -> bool {
+        share.nonce == self.nonce
+            && share.title == self.title
+            && share.bits == self.bits
+            && share.version == self.version
+    }
+
+    /// Same as [`SetInProgress::would_accept`].
+    fn would_accept(&self, new_share: &Share) -> Result<(), BananaError> {
+        self.would_accept_lenient(new_share)?;
+        if let Some(existing_id) = self.content_duplicate_of(new_share) {
+            return Err(BananaError::ShareContentDuplicate {
+                existing_id,
+                new_id: new_share.id,
+            });
+        } // ... and distinct content
+
+        Ok(())
+    }
+
+    /// Same as [`SetInProgress::would_accept_lenient`].
+    fn would_accept_lenient(&self, new_share: &Share) -> Result<(), BananaError> {
+        if new_share.version != self.version {
+            return Err(BananaError::ShareVersionDifferent);
+        } // should have same version
+
+        if new_share.title != self.title {
+            return Err(BananaError::ShareTitleDifferent {
+                set: self.title.to_owned(),
+                new_share: new_share.title.to_owned(),
+            });
+        } // ... and same title
+
+        if new_share.required_shares != self.required_shares {
+            return Err(BananaError::ShareRequiredSharesDifferent);
+        } // ... and same number of required shares
+
+        if new_share.nonce != self.nonce {
+            return Err(BananaError::ShareNonceDifferent);
+        } // ... and same nonce
+
+        if new_share.bits != self.bits {
+            return Err(BananaError::ShareBitsDifferent);
+        } // ... and bits
+
+        if self.id_set.contains(&new_share.id) {
+            return Err(BananaError::ShareAlreadyInSet);
+        } // ... also should be a new share
+
+        if self.content_length != new_share.content.len() {
+            return Err(BananaError::ShareContentLengthDifferent);
+        } // ... with same content length
+
+        Ok(())
+    }
+
+    /// Id of the share in set with content identical to that of the new
+    /// [`Share`] with different id.
+    fn content_duplicate_of(&self, new_share: &Share) -> Option<u32> {
+        self.id_set
+            .iter()
+            .zip(self.content_set.iter())
+            .find(|(id, content)| **id != new_share.id && **content == new_share.content)
+            .map(|(id, _)| *id)
+    }
+
+    /// Same as [`SetInProgress::mismatch`].
+    fn mismatch(&self, new_share: &Share) -> ShareMismatch {
+        ShareMismatch {
+            version: new_share.version != self.version,
+            title: new_share.title != self.title,
+            required_shares: new_share.required_shares != self.required_shares,
+            nonce: new_share.nonce != self.nonce,
+            bits: new_share.bits != self.bits,
+            already_in_set: self.id_set.contains(&new_share.id),
+            content_length: new_share.content.len() != self.content_length,
+            content_duplicate: self.content_duplicate_of(new_share).is_some(),
+        }
+    }
+
+    /// Same as [`SetInProgress::derive_new_share`].
+    fn derive_new_share(&self, new_id: u32) -> Result<Share, BananaError> {
+        self.check_threshold()?;
+        if new_id == 0 || new_id >= 2u32.pow(self.bits) {
+            return Err(BananaError::ShareIdInvalid(new_id));
+        }
+        if self.id_set.contains(&new_id) {
+            return Err(BananaError::ShareAlreadyInSet);
+        }
+        let content_set: Vec<&[u8]> = self.content_set.iter().map(|a| a.as_slice()).collect();
+        let content = content_at(
+            new_id,
+            self.id_set,
+            &content_set,
+            self.tables.get(self.bits),
+            self.bits,
+        )?;
+        Ok(Share {
+            version: self.version,
+            title: self.title.to_owned(),
+            required_shares: self.required_shares,
+            nonce: self.nonce.to_owned(),
+            bits: self.bits,
+            id: new_id,
+            content,
+        })
+    }
+
+    /// Set of the shares with given ids only, in given order.
+    ///
+    /// Each id must be in set and appear only once, and there must be at
+    /// least the required number of ids.
+    fn subset(&self, ids: &[u32]) -> Result<SetInProgress, BananaError> {
+        let mut content_set: Vec<Vec<u8>> = Vec::with_capacity(ids.len());
+        for (i, id) in ids.iter().enumerate() {
+            // each share could be used only once
+            if ids[..i].contains(id) {
+                return Err(BananaError::ShareAlreadyInSet);
+            }
+            match self.id_set.iter().position(|x| x == id) {
+                Some(a) => content_set.push(self.content_set[a].to_owned()),
+                None => {
+                    content_set.zeroize();
+                    return Err(BananaError::ShareIdNotInSet(*id));
+                }
+            }
+        }
+        let subset = SetInProgress {
+            version: self.version,
+            title: self.title.to_owned(),
+            required_shares: self.required_shares,
+            nonce: self.nonce.to_owned(),
+            bits: self.bits,
+            id_set: ids.to_vec(),
+            content_length: self.content_length,
+            content_set,
+            exhausted: false,
+            tables: TablesCache::default(),
+        };
+        subset.view().check_threshold()?;
+        Ok(subset)
+    }
+
+    /// Same as [`SetInProgress::combine_with`].
+    ///
+    /// Subset is combined with the tables of the whole set.
+    fn combine_with(&self, ids: &[u32]) -> Result<SetCombined, BananaError> {
+        let subset = self.subset(ids)?;
+        #[cfg(feature = "tracing")]
+        let _span = subset.combine_span().entered();
+        let tables = self.tables.get(self.bits);
+        let result = subset.raw_bits_with_tables(tables.logs(), tables.exps())?;
+        subset.complete(result)
+    }
+
+    /// Reconstructed bit sequence of the shares with given ids, using given
+    /// pre-calculated logarithms and exponents.
+    fn combine_subset(
+        &self,
+        ids: &[u32],
+        tables: &FieldTables,
+    ) -> Result<BitVec<u8, Msb0>, BananaError> {
+        self.subset(ids)?
+            .raw_bits_with_tables(tables.logs(), tables.exps())
+    }
+
+    /// Same as [`SetInProgress::consistency_check`].
+    fn consistency_check(&self) -> Result<ConsistencyReport, BananaError> {
+        if self.id_set.len() <= self.required_shares {
+            return Err(BananaError::ThresholdUnreachable {
+                have: self.id_set.len(),
+                need: self.required_shares + 1,
+            });
+        }
+        let tables = self.tables.get(self.bits);
+
+        // distinct combined results, zeroized on drop, also on error
+        let mut results: Vec<SubsetResult> = Vec::new();
+        let mut positions: Vec<usize> = (0..self.required_shares).collect();
+        let mut subsets_checked = 0;
+        loop {
+            let ids: Vec<u32> = positions.iter().map(|a| self.id_set[*a]).collect();
+            let mut raw_bits = self.combine_subset(&ids, tables)?;
+            let result = match results.iter_mut().find(|a| a.raw_bits == raw_bits) {
+                Some(known) => {
+                    raw_bits.as_raw_mut_slice().zeroize();
+                    known
+                }
+                None => {
+                    results.push(SubsetResult {
+                        raw_bits,
+                        subsets: 0,
+                        covered: vec![false; self.id_set.len()],
+                    });
+                    results.last_mut().expect("just added")
+                }
+            };
+            result.subsets += 1;
+            for position in positions.iter() {
+                result.covered[*position] = true;
+            }
+            subsets_checked += 1;
+            if subsets_checked == MAX_CONSISTENCY_SUBSETS
+                || !next_combination(&mut positions, self.id_set.len())
+            {
+                break;
+            }
+        }
+
+        // shares not used for the single most common result are suspected
+        let consistent = results.len() == 1;
+        let mut suspects = Vec::new();
+        if !consistent {
+            let most = results.iter().map(|a| a.subsets).max();
+            let mut majority = results.iter().filter(|a| Some(a.subsets) == most);
+            if let (Some(result), None) = (majority.next(), majority.next()) {
+                for (id, covered) in self.id_set.iter().zip(result.covered.iter()) {
+                    if !covered {
+                        suspects.push(*id);
+                    }
+                }
+            }
+        }
+
+        Ok(ConsistencyReport {
+            subsets_checked,
+            consistent,
+            suspects,
+        })
+    }
+
+    /// Set has at least the required number of shares.
+    fn check_threshold(&self) -> Result<(), BananaError> {
+        if self.id_set.len() < self.required_shares {
+            Err(BananaError::ThresholdUnreachable {
+                have: self.id_set.len(),
+                need: self.required_shares,
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Share in the set at given position, rebuilt.
+    fn share(&self, index: usize) -> Share {
+        Share {
+            version: self.version,
+            title: self.title.to_owned(),
+            required_shares: self.required_shares,
+            nonce: self.nonce.to_owned(),
+            bits: self.bits,
+            id: self.id_set[index],
+            content: self.content_set[index].to_owned(),
+        }
+    }
+
+    /// Shares in the set, rebuilt.
+    pub(crate) fn shares(&self) -> Vec<Share> {
+        (0..self.id_set.len()).map(|i| self.share(i)).collect()
+    }
+}
+
+/// Share collecting progress, from [`SetInProgress::progress`].
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Progress {
+    /// Current number of shares in set.
+    pub collected: usize,
+
+    /// Required number of shares.
+    pub required: usize,
+
+    /// Number of shares still needed.
+    pub missing: usize,
+
+    /// Share set title.
+    pub title: String,
+
+    /// Bits value of the set shares.
+    pub bits: u32,
+}
+
+/// Combined shares data.
+///
+/// Retains the combined shares, so that a lost share could be regenerated.
+/// Retained shares are enough to recover the secret with the passphrase, so
+/// `SetCombined` is exactly as sensitive as the shares themselves, also when
+/// serialized or cloned.
+///
+/// `Debug` output does not include combined data, shares contents and nonce.
+///
+/// Combined data, shares contents and nonce are zeroized on drop, in clones
+/// as well.
+#[derive(Clone)]
+pub struct SetCombined {
+    title: String,
+    data: Vec<u8>,
+    nonce: Vec<u8>,
+    version: Version,
+    required_shares: usize,
+    bits: u32,
+    id_set: Vec<u32>,
+    content_set: Vec<Vec<u8>>,
+    tables: TablesCache,
+    extra_ids: Vec<u32>,
+}
+
+impl SetCombined {
+    /// Recover the secret with user-provided passphrase.
+    ///
+    /// Returned secret is not zeroized,
+    /// [`SetCombined::recover_with_passphrase_secure`] is preferred.
+    pub fn recover_with_passphrase(&self, passphrase: &str) -> Result<String, BananaError> {
+        self.recover_with_passphrase_bytes(passphrase.as_bytes())
+    }
+
+    /// Recover the secret with user-provided passphrase, given as exact bytes
+    /// used in key derivation.
+    ///
+    /// Upstream banana split tool uses UTF-8 encoding of the passphrase as
+    /// displayed, same as [`SetCombined::recover_with_passphrase`]. This is
+    /// for trying byte-level variants of passphrases with characters mangled
+    /// on input, such as smart quotes or non-breaking spaces.
+    pub fn recover_with_passphrase_bytes(&self, passphrase: &[u8]) -> Result<String, BananaError> {
+        let mut decrypted = Zeroizing::new(self.decrypt(passphrase)?);
+        secret_string(core::mem::take(&mut *decrypted))
+    }
+
+    /// Recover the secret with user-provided passphrase, zeroized on drop.
+    pub fn recover_with_passphrase_secure(
+        &self,
+        passphrase: &str,
+    ) -> Result<Zeroizing<String>, BananaError> {
+        Ok(Zeroizing::new(self.recover_with_passphrase(passphrase)?))
+    }
+
+    /// Recover the secret with user-provided passphrase, as raw bytes.
+    ///
+    /// For binary secrets, such as raw entropy or keystore files. Returned
+    /// data is zeroized on drop.
+    pub fn recover_bytes_with_passphrase(
+        &self,
+        passphrase: &str,
+    ) -> Result<Zeroizing<Vec<u8>>, BananaError> {
+        Ok(Zeroizing::new(self.decrypt(passphrase.as_bytes())?))
+    }
+
+    /// Recover the secret with user-provided passphrase, using non-default
+    /// scrypt parameters.
+    ///
+    /// This is for shares made by generators that used scrypt parameters
+    /// different from the banana split ones (`log_n = 15`, `r = 8`, `p = 1`).
+    /// Salt and decryption are same as usual.
+    pub fn recover_with_passphrase_params(
+        &self,
+        passphrase: &str,
+        params: Params,
+    ) -> Result<String, BananaError> {
+        let key = derive_key_with_params(passphrase.as_bytes(), &self.salt(), &params)?;
+        secret_string(self.decrypt_with_cipher(&key.cipher())?)
+    }
+
+    /// Recover the secret with user-provided passphrase, reporting coarse
+    /// progress.
+    ///
+    /// Key derivation runs on a spawned thread, and while it runs `callback`
+    /// is called every [`PROGRESS_HEARTBEAT`] with slowly growing value, so
+    /// that the user interface could show that the recovery is not stuck.
+    /// Reported values are:
+    ///
+    /// - `0.1` after the salt is hashed
+    /// - heartbeat values approaching `0.9` while the key is derived
+    /// - `0.9` when the key is derived and the decryption starts
+    /// - `1.0` when the recovery is done, successfully or not
+    ///
+    /// Key derivation time can not be known beforehand, so the heartbeat
+    /// values are not a time estimate.
+    #[cfg(feature = "std")]
+    pub fn recover_with_passphrase_with_progress(
+        &self,
+        passphrase: &str,
+        mut callback: impl FnMut(f32),
+    ) -> Result<String, BananaError> {
+        let salt = self.salt();
+        let params = self.version.spec().scrypt_params();
+        callback(0.1);
+
+        let key = std::thread::scope(|scope| {
+            let (sender, receiver) = std::sync::mpsc::channel();
+            let _ = scope.spawn(move || {
+                // receiver is gone only if the scope is unwinding
+                let _ = sender.send(derive_key_with_params(
+                    passphrase.as_bytes(),
+                    &salt,
+                    &params,
+                ));
+            });
+            let mut progress = 0.1;
+            loop {
+                match receiver.recv_timeout(PROGRESS_HEARTBEAT) {
+                    Ok(key) => break key,
+                    Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+                        progress += (0.9 - progress) / 8.0;
+                        callback(progress);
+                    }
+                    Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
+                        break Err(BananaError::ScryptFailed)
+                    }
+                }
+            }
+        });
+        callback(0.9);
+
+        let result = key.and_then(|key| secret_string(self.decrypt_with_cipher(&key.cipher())?));
+        callback(1.0);
+        result
+    }
+
+    /// Recover the secret trying typo-tolerant variants of user-provided
+    /// passphrase.
+    ///
+    /// Variants are tried in fixed order, at most `options.max_attempts` of
+    /// them, see [`PassphraseVariant`]. The first variant that decrypts the
+    /// secret is returned together with the secret. If none does,
+    /// [`BananaError::DecodingFailed`] is returned. Key derived for each
+    /// failed attempt is zeroized right away.
+    pub fn recover_with_candidates(
+        &self,
+        base: &str,
+        options: CandidateOptions,
+    ) -> Result<(String, PassphraseVariant), BananaError> {
+        let salt = self.salt();
+        for (variant, candidate) in candidates(base, &options) {
+            let key = self.derive_key_with_salt(candidate.as_bytes(), &salt)?;
+            match self.decrypt_with_cipher(&key.cipher()) {
+                Ok(decrypted) => return Ok((secret_string(decrypted)?, variant)),
+                Err(BananaError::DecodingFailed) => (),
+                Err(e) => return Err(e),
+            }
+        }
+        Err(BananaError::DecodingFailed)
+    }
+
+    /// Recover the secret with user-provided passphrase, retrying with
+    /// whitespace-trimmed passphrase.
+    ///
+    /// Passphrases pasted from elsewhere often get leading or trailing
+    /// whitespace. If decoding with the passphrase as is fails, and trimming
+    /// changes the passphrase, the recovery is tried once more with the
+    /// trimmed passphrase. Note that trimmed passphrase is a different
+    /// passphrase, with a different derived key, and this doubles the time
+    /// spent on failed attempts.
+    pub fn recover_with_passphrase_trimmed(&self, passphrase: &str) -> Result<String, BananaError> {
+        match self.recover_with_passphrase(passphrase) {
+            Err(BananaError::DecodingFailed) if passphrase.trim() != passphrase => {
+                self.recover_with_passphrase(passphrase.trim())
+            }
+            a => a,
+        }
+    }
+
+    /// Recover the secret with user-provided passphrase, for secrets stored
+    /// as UTF-16LE text.
+    ///
+    /// Some legacy generators encrypted the secret as UTF-16LE rather than
+    /// UTF-8. Decrypted data of odd length or with invalid surrogate
+    /// sequences results in [`BananaError::DecodedSecretNotString`].
+    pub fn recover_utf16le(&self, passphrase: &str) -> Result<String, BananaError> {
+        let mut decrypted = self.decrypt(passphrase.as_bytes())?;
+
+        // decoded string is a new allocation, decrypted data is always zeroized;
+        // each UTF-16 unit takes at most 3 bytes in UTF-8, so the string is
+        // never reallocated leaving unzeroized copies behind
+        let mut out = String::with_capacity(decrypted.len() / 2 * 3);
+        let mut valid = decrypted.len() % 2 == 0;
+        if valid {
+            for ch in char::decode_utf16(
+                decrypted
+                    .chunks_exact(2)
+                    .map(|pair| u16::from_le_bytes([pair[0], pair[1]])),
+            ) {
+                match ch {
+                    Ok(a) => out.push(a),
+                    Err(_) => {
+                        valid = false;
+                        break;
+                    }
+                }
+            }
+        }
+        decrypted.zeroize();
+
+        if valid {
+            Ok(out)
+        } else {
+            out.zeroize();
+            Err(BananaError::DecodedSecretNotString)
+        }
+    }
+
+    /// Recover the secret with user-provided passphrase, for shares made by
+    /// generators using Argon2id key derivation instead of scrypt.
+    ///
+    /// Key is derived with Argon2id (version `0x13`) and caller-provided
+    /// `params` over the same salt, i.e. hashed set title. Decryption is done
+    /// as usual.
+    #[cfg(feature = "argon2")]
+    pub fn recover_with_argon2(
+        &self,
+        passphrase: &str,
+        params: argon2::Params,
+    ) -> Result<String, BananaError> {
+        let mut key = [0u8; 32];
+        argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), &self.salt(), &mut key)
+            .map_err(|_| BananaError::Argon2Failed)?;
+        let cipher = XSalsa20Poly1305::new(GenericArray::from_slice(&key[..]));
+        key.zeroize();
+        secret_string(self.decrypt_with_cipher(&cipher)?)
+    }
+
+    /// Recover the secret with user-provided passphrase, for secrets
+    /// compressed before encryption.
+    ///
+    /// Decrypted data must be in gzip or zlib format, detected by the magic
+    /// header. Malformed compressed data results in
+    /// [`BananaError::DecompressionFailed`].
+    #[cfg(feature = "compression")]
+    pub fn recover_decompressed(&self, passphrase: &str) -> Result<String, BananaError> {
+        let mut decrypted = self.decrypt(passphrase.as_bytes())?;
+        let decompressed = crate::compression::decompress(&decrypted);
+        decrypted.zeroize();
+        secret_string(decompressed?)
+    }
+
+    /// Recover the secret with user-provided passphrase, and verify it by
+    /// re-encrypting.
+    ///
+    /// Decrypted secret is encrypted again with the same key and nonce, and
+    /// the result must match the combined data exactly. Otherwise,
+    /// [`BananaError::VerificationMismatch`] is returned. Intended for
+    /// high-assurance recovery, where even a MAC collision must be ruled out.
+    pub fn recover_verified(&self, passphrase: &str) -> Result<String, BananaError> {
+        let cipher = self
+            .derive_key_with_salt(passphrase.as_bytes(), &self.salt())?
+            .cipher();
+        let nonce = GenericArray::from_slice(&self.nonce[..]);
+        let mut decrypted = cipher
+            .decrypt(nonce, self.data.as_ref())
+            .map_err(|_| BananaError::DecodingFailed)?;
+        let verified = match cipher.encrypt(nonce, decrypted.as_ref()) {
+            Ok(mut encrypted) => {
+                // compared in constant time, same as the secret hash
+                let verified = encrypted.len() == self.data.len()
+                    && encrypted
+                        .iter()
+                        .zip(self.data.iter())
+                        .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+                        == 0;
+                encrypted.zeroize();
+                verified
+            }
+            Err(_) => false,
+        };
+        if verified {
+            secret_string(decrypted)
+        } else {
+            decrypted.zeroize();
+            Err(BananaError::VerificationMismatch)
+        }
+    }
+
+    /// Recover the secret with user-provided passphrase, and check it against
+    /// the SHA-256 hash recorded at backup time.
+    ///
+    /// Hash of the recovered secret bytes is compared with `expected_sha256`
+    /// in constant time. If the hashes differ,
+    /// [`BananaError::SecretHashMismatch`] is returned, and the recovered
+    /// secret is zeroized.
+    pub fn recover_and_check_hash(
+        &self,
+        passphrase: &str,
+        expected_sha256: &[u8; 32],
+    ) -> Result<String, BananaError> {
+        let mut secret = self.recover_with_passphrase(passphrase)?;
+        let mut hash: [u8; 32] = Sha256::digest(secret.as_bytes()).into();
+        let difference = hash
+            .iter()
+            .zip(expected_sha256.iter())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b));
+        hash.zeroize();
+        if difference == 0 {
+            Ok(secret)
+        } else {
+            secret.zeroize();
+            Err(BananaError::SecretHashMismatch)
+        }
+    }
+
+    /// Recover the secret with user-provided passphrase directly into
+    /// caller-provided [`SecretSink`].
+    ///
+    /// Decrypted data is passed to the sink as is, without any text
+    /// conversion, and the internal buffer is zeroized afterwards.
+    pub fn recover_into_sink(
+        &self,
+        passphrase: &str,
+        sink: &mut impl SecretSink,
+    ) -> Result<(), BananaError> {
+        let mut decrypted = self.decrypt(passphrase.as_bytes())?;
+        sink.write(&decrypted);
+        decrypted.zeroize();
+        Ok(())
+    }
+
+    /// Collect and combine shares produced by an iterator.
+    ///
+    /// Shares are consumed one by one until the required number of shares is
+    /// reached. If `stop_when_ready` is `true`, the remaining shares are not
+    /// consumed at all. Otherwise the iterator is exhausted, with the extra
+    /// shares checked for compatibility, but not used.
+    ///
+    /// The first error, from the iterator itself or from adding the share to
+    /// the set, is returned as is. If the iterator ends before the set gets
+    /// combined, [`BananaError::InsufficientShares`] is returned, or
+    /// [`BananaError::NoShares`] if there were no shares at all.
+    pub fn from_share_iter(
+        shares: impl Iterator<Item = Result<Share, BananaError>>,
+        stop_when_ready: bool,
+    ) -> Result<SetCombined, BananaError> {
+        let mut share_collection = ShareCollection::new();
+        for share in shares {
+            let outcome = share_collection.add_share(share?)?;
+            if stop_when_ready && outcome == AddOutcome::Ready {
+                break;
+            }
+        }
+        match share_collection {
+            ShareCollection::Empty => Err(BananaError::NoShares),
+            ShareCollection::InProgress(in_progress) => Err(BananaError::InsufficientShares {
+                have: in_progress.shares_now(),
+                need: in_progress.shares_required(),
+            }),
+            ShareCollection::Ready(combined) => Ok(combined),
+        }
+    }
+
+    /// Regenerate a share, for example to replace a lost one.
+    ///
+    /// The secret is decrypted first with user-provided passphrase, only to
+    /// make sure the passphrase and the combined shares are correct. The
+    /// decrypted secret is zeroized right away, and is not used otherwise.
+    ///
+    /// Shares with `existing_ids` are taken from the combined set, there must
+    /// be at least the required number of them. Shamir polynomial is
+    /// interpolated from these shares contents, and is evaluated at
+    /// `target_id`, producing a share that could be used with any other
+    /// shares of the set. Threshold is not changed.
+    ///
+    /// `target_id` must be a new one. Share already in the combined set, or
+    /// among `existing_ids`, results in [`BananaError::ShareAlreadyInSet`],
+    /// same as in [`SetInProgress::derive_new_share`].
+    ///
+    /// As in [`SetInProgress::derive_new_share`], for bits above `8` the
+    /// share content could be not representable, resulting in
+    /// [`BananaError::DerivedShareUnrepresentable`].
+    pub fn regenerate_share(
+        &self,
+        passphrase: &str,
+        target_id: u32,
+        existing_ids: &[u32],
+    ) -> Result<Share, BananaError> {
+        let mut decrypted = self.decrypt(passphrase.as_bytes())?;
+        decrypted.zeroize();
+
+        if target_id == 0 || target_id >= 2u32.pow(self.bits) {
+            return Err(BananaError::ShareIdInvalid(target_id));
+        }
+        if self.id_set.contains(&target_id) || existing_ids.contains(&target_id) {
+            return Err(BananaError::ShareAlreadyInSet);
+        }
+
+        // pick the existing shares contents
+        let mut content_set: Vec<&[u8]> = Vec::with_capacity(existing_ids.len());
+        for (i, id) in existing_ids.iter().enumerate() {
+            // each share could be used only once
+            if existing_ids[..i].contains(id) {
+                return Err(BananaError::ShareAlreadyInSet);
+            }
+            match self.id_set.iter().position(|x| x == id) {
+                Some(a) => content_set.push(&self.content_set[a]),
+                None => return Err(BananaError::ShareIdNotInSet(*id)),
+            }
+        }
+        // set made from parts has no shares at all
+        if existing_ids.len() < self.required_shares.max(1) {
+            return Err(BananaError::ThresholdUnreachable {
+                have: existing_ids.len(),
+                need: self.required_shares.max(1),
+            });
+        }
+
+        let content = content_at(
+            target_id,
+            existing_ids,
+            &content_set,
+            self.tables.get(self.bits),
+            self.bits,
+        )?;
+        Ok(Share {
+            version: self.version,
+            title: self.title.to_owned(),
+            required_shares: self.required_shares,
+            nonce: self.nonce.to_owned(),
+            bits: self.bits,
+            id: target_id,
+            content,
+        })
+    }
+
+    /// Cross-check the combined shares, if there are more shares than
+    /// required.
+    ///
+    /// Same as [`SetInProgress::consistency_check`], for all the combined
+    /// shares.
+    pub fn consistency_check(&self) -> Result<ConsistencyReport, BananaError> {
+        self.view().consistency_check()
+    }
+
+    /// Derive a brand-new share of the set, without the passphrase.
+    ///
+    /// Same as [`SetInProgress::derive_new_share`], for all the combined
+    /// shares.
+    pub fn derive_new_share(&self, new_id: u32) -> Result<Share, BananaError> {
+        // set made from parts has no shares at all
+        if self.id_set.is_empty() {
+            return Err(BananaError::NoShares);
+        }
+        self.view().derive_new_share(new_id)
+    }
+
+    /// Borrowed view of the combined shares.
+    pub(crate) fn view(&self) -> SetView<'_> {
+        SetView {
+            version: self.version,
+            title: &self.title,
+            required_shares: self.required_shares,
+            nonce: &self.nonce,
+            bits: self.bits,
+            id_set: &self.id_set,
+            content_length: self.content_set.first().map_or(0, |a| a.len()),
+            content_set: &self.content_set,
+            tables: &self.tables,
+        }
+    }
+
+    /// Set of the combined shares, before combining, taken out of the
+    /// combined set.
+    ///
+    /// Combined set is left with no shares.
+    fn take_in_progress(&mut self) -> SetInProgress {
+        SetInProgress {
+            version: self.version,
+            title: core::mem::take(&mut self.title),
+            required_shares: self.required_shares,
+            nonce: core::mem::take(&mut self.nonce),
+            bits: self.bits,
+            content_length: self.content_set.first().map_or(0, |a| a.len()),
+            id_set: core::mem::take(&mut self.id_set),
+            content_set: core::mem::take(&mut self.content_set),
+            exhausted: false,
+            tables: core::mem::take(&mut self.tables),
+        }
+    }
+
+    /// Derive the key from user-provided passphrase.
+    ///
+    /// This is the expensive part of the recovery, scrypt key derivation with
+    /// hashed title as salt. The key could be used with
+    /// [`SetCombined::decrypt_with_key`], for example the key derivation
+    /// could run on a worker thread.
+    pub fn derive_key(&self, passphrase: &str) -> Result<RecoveryKey, BananaError> {
+        self.derive_key_with_salt(passphrase.as_bytes(), &self.salt())
+    }
+
+    /// Derive the key from user-provided passphrase, using pre-calculated
+    /// salt and the scrypt parameters of the set version.
+    fn derive_key_with_salt(
+        &self,
+        passphrase: &[u8],
+        salt: &[u8],
+    ) -> Result<RecoveryKey, BananaError> {
+        derive_key_with_params(passphrase, salt, &self.version.spec().scrypt_params())
+    }
+
+    /// Recover the secret with the key derived from the passphrase.
+    pub fn decrypt_with_key(&self, key: &RecoveryKey) -> Result<String, BananaError> {
+        secret_string(self.decrypt_with_cipher(&key.cipher())?)
+    }
+
+    /// Start [`RecoverySession`], for trying several passphrases.
+    pub fn session(&self) -> RecoverySession<'_> {
+        RecoverySession {
+            combined: self,
+            salt: self.salt(),
         }
     }
 
-    /// Add new [`Share`] to existing set.
-    fn add_share(&mut self, new_share: Share) -> Result<(), BananaError> {
-        if new_share.version != self.version {
-            return Err(BananaError::ShareVersionDifferent);
-        } // should have same version
+    /// Scrypt salt, SHA-512 hash of the set title.
+    ///
+    /// Together with [`SetCombined::encrypted_data`] and
+    /// [`SetCombined::nonce`] this is all that is needed to decrypt the
+    /// secret elsewhere, the same way this crate does:
+    ///
+    /// - 32-byte key is derived with scrypt, `log_n = 15`, `r = 8`, `p = 1`,
+    ///   from UTF-8 bytes of the passphrase and this salt
+    /// - encrypted data is decrypted with XSalsa20Poly1305 (NaCl
+    ///   `secretbox`), with the derived key and the nonce
+    pub fn salt(&self) -> [u8; 64] {
+        title_salt(&self.title)
+    }
 
-        if new_share.title != self.title {
-            return Err(BananaError::ShareTitleDifferent {
-                set: self.title(),
-                new_share: new_share.title,
-            });
-        } // ... and same title
+    /// Encrypted secret, as combined from the shares.
+    ///
+    /// This is NaCl `secretbox` output, i.e. Poly1305 tag followed by the
+    /// ciphertext.
+    pub fn encrypted_data(&self) -> &[u8] {
+        &self.data
+    }
 
-        if new_share.required_shares != self.required_shares {
-            return Err(BananaError::ShareRequiredSharesDifferent);
-        } // ... and same number of required shares
+    /// Decrypt the combined data with user-provided passphrase.
+    fn decrypt(&self, passphrase: &[u8]) -> Result<Vec<u8>, BananaError> {
+        self.decrypt_with_salt(passphrase, &self.salt())
+    }
 
-        if new_share.nonce != self.nonce {
-            return Err(BananaError::ShareNonceDifferent);
-        } // ... and same nonce
+    /// Decrypt the combined data with user-provided passphrase, using
+    /// pre-calculated salt.
+    fn decrypt_with_salt(&self, passphrase: &[u8], salt: &[u8]) -> Result<Vec<u8>, BananaError> {
+        self.decrypt_with_cipher(&self.derive_key_with_salt(passphrase, salt)?.cipher())
+    }
 
-        if new_share.bits != self.bits {
-            return Err(BananaError::ShareBitsDifferent);
-        } // ... and bits
+    /// Decrypt the combined data with cipher set up elsewhere.
+    fn decrypt_with_cipher(&self, cipher: &XSalsa20Poly1305) -> Result<Vec<u8>, BananaError> {
+        // decrypt secret using nonce
+        let result = cipher
+            .decrypt(
+                GenericArray::from_slice(&self.nonce[..]),
+                self.data.as_ref(),
+            )
+            .map_err(|_| BananaError::DecodingFailed);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(success = result.is_ok(), "decryption attempted");
+        result
+    }
 
-        if self.id_set.contains(&new_share.id) {
-            return Err(BananaError::ShareAlreadyInSet);
-        } // ... also should be a new share
+    /// Share set title.
+    pub fn title(&self) -> &str {
+        &self.title
+    }
 
-        if self.content_length != new_share.content.len() {
-            return Err(BananaError::ShareContentLengthDifferent);
-        } // ... with same content length
+    /// Ids of the combined shares.
+    pub fn ids(&self) -> &[u32] {
+        &self.id_set
+    }
 
-        self.id_set.push(new_share.id);
-        self.content_set.push(new_share.content);
+    /// Decoded nonce.
+    pub fn nonce(&self) -> &[u8] {
+        &self.nonce
+    }
 
-        Ok(())
+    /// Nonce re-encoded in base64 format, as in share json.
+    pub fn nonce_base64(&self) -> String {
+        base64::encode(&self.nonce)
     }
 
-    /// Combine `SetInProgress` into [`SetCombined`].
+    /// New `SetCombined` from raw parts, for data combined elsewhere.
     ///
-    /// Function must be applied only if the set is checked elsewhere to have at
-    /// least the required number of shares.
-    fn combine(&self) -> Result<SetCombined, BananaError> {
-        // transpose content set
-        // from
-        // `Vec[[share1[1], share1[2] ... share1[N]], [share2[1], share2[2] ... share2[N]] ... [shareM[1], shareM[2] ... shareM[N]]]`
-        // into
-        // `Vec[[share1[1], share2[1] ... shareM[1]], [share1[2], share2[2] ... shareM[2]] ... [share1[N], share2[N] ... shareM[N]]]`
-        let mut content_zipped: Vec<Vec<u32>> = Vec::with_capacity(self.content_length);
-        for i in 0..self.content_length {
-            let mut new: Vec<u32> = Vec::new();
-            for j in 0..self.id_set.len() {
-                new.push(self.content_set[j][i] as u32)
-            }
-            content_zipped.push(new);
+    /// `data` is the encrypted secret, see [`SetCombined::encrypted_data`],
+    /// and `nonce` must be 24 bytes long, otherwise
+    /// [`BananaError::NonceWrongLength`] is returned. Set made from parts
+    /// has no shares, so the shares could not be regenerated from it.
+    pub fn from_parts(title: &str, data: Vec<u8>, nonce: Vec<u8>) -> Result<Self, BananaError> {
+        if nonce.len() != NONCE_LENGTH {
+            return Err(BananaError::NonceWrongLength(nonce.len()));
         }
+        Ok(Self {
+            title: title.to_owned(),
+            data,
+            nonce,
+            version: Version::V1,
+            required_shares: 0,
+            bits: V1_BITS,
+            id_set: Vec::new(),
+            content_set: Vec::new(),
+            tables: TablesCache::default(),
+            extra_ids: Vec::new(),
+        })
+    }
+}
 
-        // calculate logarithms and exponents in `GF(2^self.bits)`
-        let (logs, exps) = generate_logs_and_exps(self.bits);
+impl Debug for Share {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("Share")
+            .field("version", &self.version)
+            .field("title", &self.title)
+            .field("required_shares", &self.required_shares)
+            .field("nonce", &Redacted(self.nonce.len()))
+            .field("bits", &self.bits)
+            .field("id", &self.id)
+            .field("content", &Redacted(self.content.len()))
+            .finish()
+    }
+}
 
-        // process and collect bit sequence from each element of content_zipped
-        let mut result: BitVec<u32, Msb0> = BitVec::new();
-        for content_zipped_element in content_zipped.iter() {
-            // new element that will be processed; is calculated as `u32`, its value is always below `2^self.bits`;
-            let new = lagrange(
-                &self.id_set,
-                content_zipped_element,
-                &logs,
-                &exps,
-                self.bits,
-            )?;
+impl Debug for SetInProgress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("SetInProgress")
+            .field("version", &self.version)
+            .field("title", &self.title)
+            .field("required_shares", &self.required_shares)
+            .field("nonce", &Redacted(self.nonce.len()))
+            .field("bits", &self.bits)
+            .field("id_set", &self.id_set)
+            .field("content_length", &self.content_length)
+            .field("exhausted", &self.exhausted)
+            .finish()
+    }
+}
 
-            // transform new element into new bitvec to operate on bits individually
-            let new_bitvec: BitVec<u32, Msb0> = BitVec::from_vec(vec![new]);
+impl Debug for SetCombined {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("SetCombined")
+            .field("title", &self.title)
+            .field("data", &Redacted(self.data.len()))
+            .field("nonce", &Redacted(self.nonce.len()))
+            .field("version", &self.version)
+            .field("required_shares", &self.required_shares)
+            .field("bits", &self.bits)
+            .field("id_set", &self.id_set)
+            .field("extra_ids", &self.extra_ids)
+            .finish()
+    }
+}
 
-            // in js code this crate follows, the bits string representation of new element (i.e. without leading zeroes)
-            // was padded from left with zeroes so that the string length became multiple of `self.bits` number;
-            // since the new element value is always below `2^self.bits`, this procedure effectively means keeping only
-            // `self.bits` amount of bits from the element;
-            // cut is the starting point after which the bits are retained;
-            let cut = (32 - self.bits) as usize;
+impl FromStr for Share {
+    type Err = BananaError;
 
-            // resulting bits are added into collection;
-            result.extend_from_bitslice(&new_bitvec[cut..]);
+    /// Share from share json string, or from QR data in hex format.
+    ///
+    /// Json is recognized by the opening brace, anything else is processed as
+    /// hex.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if trim_payload(s).starts_with('{') {
+            Self::from_json_str(s)
+        } else {
+            Self::from_hex_payload(s)
         }
+    }
+}
 
-        // the js code this crate follows then calls for cutting all leading false bits
-        // up until the first true, which serves as a padding marker,
-        // cut padding marker as well, and then collect bytes with some padding on the left if necessary
-        let result: BitVec<u8, Msb0> = result.into_iter().skip_while(|x| !*x).skip(1).collect();
-
-        // transform result in its final form, `Vec<u8>`
-        let data = result.into_vec();
-
-        // process nonce, so that it is done before asking for a password
-        let nonce = match base64::decode(self.nonce.as_bytes()) {
-            Ok(a) => a,
-            Err(_) => return Err(BananaError::NonceNotBase64),
-        };
-
-        // now the set is ready
-        Ok(SetCombined {
-            data,
-            nonce,
-            title: self.title.to_owned(),
-        })
+impl Drop for Share {
+    fn drop(&mut self) {
+        self.content.zeroize();
+        self.nonce.zeroize();
     }
+}
 
-    /// Current number of shares in set.
-    pub fn shares_now(&self) -> usize {
-        self.id_set.len()
+impl Drop for SetInProgress {
+    fn drop(&mut self) {
+        self.content_set.zeroize();
+        self.nonce.zeroize();
     }
+}
 
-    /// Required number of shares.
-    pub fn shares_required(&self) -> usize {
-        self.required_shares
+impl Drop for SetCombined {
+    fn drop(&mut self) {
+        self.data.zeroize();
+        self.content_set.zeroize();
+        self.nonce.zeroize();
     }
+}
 
-    /// Share set title.
-    pub fn title(&self) -> String {
-        self.title.to_owned()
+/// Placeholder for a byte buffer in `Debug` output, with buffer length.
+struct Redacted(usize);
+
+impl Debug for Redacted {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "[{} bytes]", self.0)
     }
 }
 
-/// Combined shares data.
+/// Interval between progress reports while the key is derived, in
+/// [`SetCombined::recover_with_passphrase_with_progress`].
+#[cfg(feature = "std")]
+pub const PROGRESS_HEARTBEAT: core::time::Duration = core::time::Duration::from_millis(100);
+
+/// Memory needed for scrypt key derivation with given parameters, in bytes.
+///
+/// This is the size of the dominant scrypt buffer, `128 * r * 2^log_n`. For
+/// default banana split parameters (`log_n = 15`, `r = 8`) it is 32 MiB.
+///
+/// Could be compared to available memory before attempting the recovery on a
+/// memory-constrained device.
+pub fn scrypt_memory_bytes(params: &Params) -> usize {
+    let n = 1usize
+        .checked_shl(params.log_n() as u32)
+        .unwrap_or(usize::MAX);
+    128usize
+        .saturating_mul(params.r() as usize)
+        .saturating_mul(n)
+}
+
+/// Recovery attempts for a [`SetCombined`], with the salt calculated once.
+///
+/// Each passphrase attempt still runs the scrypt key derivation, this is
+/// unavoidable.
 #[derive(Debug)]
-pub struct SetCombined {
-    title: String,
-    data: Vec<u8>,
-    nonce: Vec<u8>,
+pub struct RecoverySession<'a> {
+    combined: &'a SetCombined,
+    salt: [u8; 64],
 }
 
-impl SetCombined {
-    /// Recover the secret with user-provided passphrase.
-    pub fn recover_with_passphrase(&self, passphrase: &str) -> Result<String, BananaError> {
-        // hash title into salt
-        let mut hasher = Sha512::new();
-        hasher.update(self.title.as_bytes());
-        let salt = hasher.finalize();
+impl RecoverySession<'_> {
+    /// Try recovering the secret with user-provided passphrase.
+    pub fn try_passphrase(&self, passphrase: &str) -> Result<String, BananaError> {
+        secret_string(
+            self.combined
+                .decrypt_with_salt(passphrase.as_bytes(), &self.salt)?,
+        )
+    }
+}
 
-        // set up the parameters for scrypt;
-        // default ones are used
-        let params = Params::new(15, 8, 1).expect("static checked params");
+/// Scrypt salt for the set with given title, hashed title.
+pub(crate) fn title_salt(title: &str) -> [u8; 64] {
+    let mut hasher = Sha512::new();
+    hasher.update(title.as_bytes());
+    hasher.finalize().into()
+}
 
-        // set up output buffer for scrypt;
-        // must allocate here, empty output buffer is rejected
-        let mut key: Vec<u8> = [0; 32].to_vec();
+/// Set up cipher with the key derived from user-provided passphrase and
+/// salt.
+pub(crate) fn cipher(passphrase: &[u8], salt: &[u8]) -> Result<XSalsa20Poly1305, BananaError> {
+    Ok(derive_key(passphrase, salt)?.cipher())
+}
 
-        // ... and scrypt them
-        scrypt(passphrase.as_bytes(), &salt, &params, &mut key)
-            .map_err(|_| BananaError::ScryptFailed)?;
+/// Derive the key from user-provided passphrase and salt, with scrypt
+/// parameters of version `V1`.
+fn derive_key(passphrase: &[u8], salt: &[u8]) -> Result<RecoveryKey, BananaError> {
+    derive_key_with_params(passphrase, salt, &Version::V1.spec().scrypt_params())
+}
 
-        // set up cipher with key and decrypt secret using nonce
-        let cipher = XSalsa20Poly1305::new(GenericArray::from_slice(&key[..]));
-        match cipher.decrypt(
-            GenericArray::from_slice(&self.nonce[..]),
-            self.data.as_ref(),
-        ) {
-            Ok(a) => match String::from_utf8(a) {
-                // in case of successful vector-to-string conversion, vector does not get copied:
-                // https://doc.rust-lang.org/std/string/struct.String.html#method.from_utf8
-                // string ptr same as the one of former vector,
-                // string goes into output, no zeroize
-                Ok(b) => Ok(b),
-
-                // in case of conversion BananaError, the vector goes into BananaError;
-                // should be zeroized
-                Err(e) => {
-                    let mut cleanup = e.into_bytes();
-                    cleanup.zeroize();
-                    Err(BananaError::DecodedSecretNotString)
-                }
-            },
-            Err(_) => Err(BananaError::DecodingFailed),
-        }
+/// Derive the key from user-provided passphrase and salt, with given scrypt
+/// parameters.
+fn derive_key_with_params(
+    passphrase: &[u8],
+    salt: &[u8],
+    params: &Params,
+) -> Result<RecoveryKey, BananaError> {
+    // set up output buffer for scrypt
+    let mut key = RecoveryKey([0; 32]);
+
+    // ... and scrypt them
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("derive_key", log_n = params.log_n()).entered();
+    scrypt(passphrase, salt, params, &mut key.0).map_err(|_| BananaError::ScryptFailed)?;
+    Ok(key)
+}
+
+/// Key derived from the passphrase, from [`SetCombined::derive_key`].
+///
+/// Zeroized on drop.
+pub struct RecoveryKey([u8; 32]);
+
+impl RecoveryKey {
+    /// Set up cipher with the key.
+    fn cipher(&self) -> XSalsa20Poly1305 {
+        XSalsa20Poly1305::new(GenericArray::from_slice(&self.0[..]))
     }
+}
 
-    /// Share set title.
-    pub fn title(&self) -> String {
-        self.title.to_owned()
+impl Drop for RecoveryKey {
+    fn drop(&mut self) {
+        self.0.zeroize()
+    }
+}
+
+impl Debug for RecoveryKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str("RecoveryKey(..)")
+    }
+}
+
+/// Transform decrypted data into secret `String`.
+fn secret_string(decrypted: Vec<u8>) -> Result<String, BananaError> {
+    match String::from_utf8(decrypted) {
+        // in case of successful vector-to-string conversion, vector does not get copied:
+        // https://doc.rust-lang.org/std/string/struct.String.html#method.from_utf8
+        // string ptr same as the one of former vector,
+        // string goes into output, no zeroize
+        Ok(a) => Ok(a),
+
+        // in case of conversion BananaError, the vector goes into BananaError;
+        // should be zeroized
+        Err(e) => {
+            let mut cleanup = e.into_bytes();
+            cleanup.zeroize();
+            Err(BananaError::DecodedSecretNotString)
+        }
     }
 }
 
+/// Caller-managed container for the recovered secret.
+///
+/// Used in [`SetCombined::recover_into_sink`], for integrators keeping the
+/// secrets in their own protected memory.
+pub trait SecretSink {
+    /// Receive the decrypted secret.
+    fn write(&mut self, bytes: &[u8]);
+}
+
 /// Primitive polynomials in Galois field `GF(2^n)`, for `3 <= n <= 20`.
 ///
 /// Value n is bits value for shares, and is limited by `BIT_RANGE` constants.
@@ -480,7 +3403,7 @@ const PRIMITIVE_POLYNOMIALS: [u32; 18] = [
 ///
 /// `bits` must be checked elsewhere to be within the acceptable `BIT_RANGE`.
 /// Will panic otherwise.
-fn primitive_polynomial(bits: u32) -> u32 {
+pub(crate) fn primitive_polynomial(bits: u32) -> u32 {
     PRIMITIVE_POLYNOMIALS[bits as usize - 3]
 }
 
@@ -496,22 +3419,138 @@ fn primitive_polynomial(bits: u32) -> u32 {
 /// All elements of field do not exceed `2^bits-1` in value and could be
 /// recorded with `bits` number of bits (this is quite self-evident, but will be
 /// needed later on).
-pub(crate) fn generate_logs_and_exps(bits: u32) -> (Vec<Option<u32>>, Vec<u32>) {
+///
+/// Undefined `log[0]` is recorded as [`LOG_UNDEFINED`].
+pub(crate) fn generate_logs_and_exps(bits: u32) -> (Vec<u32>, Vec<u32>) {
+    generate_logs_and_exps_with_polynomial(bits, primitive_polynomial(bits))
+}
+
+/// Marker for undefined logarithm in tables of logarithms.
+///
+/// Only `log[0]` is undefined for a primitive polynomial. Actual logarithms
+/// are always below `2^bits - 1`, so the marker never clashes with them.
+pub(crate) const LOG_UNDEFINED: u32 = u32::MAX;
+
+/// Content length starting from which the set is combined in parallel, with
+/// `rayon` feature. Shorter sets are not worth spawning the work for.
+#[cfg(feature = "rayon")]
+const PARALLEL_CONTENT_LENGTH: usize = 1024;
+
+/// Tables of logarithms and exponents in `GF(2^bits)`.
+#[derive(Clone)]
+pub(crate) enum FieldTables {
+    /// Tables for `bits = 8`, calculated at compile time.
+    Builtin,
+
+    /// Tables calculated at runtime for other `bits` values.
+    Generated { logs: Vec<u32>, exps: Vec<u32> },
+}
+
+impl FieldTables {
+    /// Tables for given `bits`.
+    ///
+    /// `bits` must be checked elsewhere to be within the acceptable
+    /// `BIT_RANGE`. Will panic otherwise.
+    pub(crate) fn new(bits: u32) -> Self {
+        if bits == V1_BITS {
+            Self::Builtin
+        } else {
+            let (logs, exps) = generate_logs_and_exps(bits);
+            Self::Generated { logs, exps }
+        }
+    }
+
+    /// Logarithms, `log[0]` is [`LOG_UNDEFINED`].
+    pub(crate) fn logs(&self) -> &[u32] {
+        match self {
+            Self::Builtin => &BUILTIN_TABLES.0,
+            Self::Generated { logs, .. } => logs,
+        }
+    }
+
+    /// Exponents.
+    pub(crate) fn exps(&self) -> &[u32] {
+        match self {
+            Self::Builtin => &BUILTIN_TABLES.1,
+            Self::Generated { exps, .. } => exps,
+        }
+    }
+}
+
+/// Tables of logarithms and exponents of a set, calculated on first use and
+/// retained.
+///
+/// Clones start empty, so that cloning a set does not copy up to 8 MiB of
+/// tables.
+#[derive(Default)]
+pub(crate) struct TablesCache(OnceBox<FieldTables>);
+
+impl TablesCache {
+    /// Tables for given `bits`, calculated if not yet present.
+    ///
+    /// `bits` must be same for all calls, as checked for the set shares.
+    fn get(&self, bits: u32) -> &FieldTables {
+        self.0.get_or_init(|| Box::new(FieldTables::new(bits)))
+    }
+}
+
+impl Clone for TablesCache {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+/// Number of elements in `GF(2^8)`.
+const BUILTIN_SIZE: usize = 1 << V1_BITS;
+
+/// Logarithms and exponents in `GF(2^8)`, for the most common `bits` value.
+static BUILTIN_TABLES: ([u32; BUILTIN_SIZE], [u32; BUILTIN_SIZE]) = builtin_tables();
+
+/// Calculate [`BUILTIN_TABLES`] at compile time, same way as in
+/// [`generate_logs_and_exps_with_polynomial`].
+const fn builtin_tables() -> ([u32; BUILTIN_SIZE], [u32; BUILTIN_SIZE]) {
+    let size = BUILTIN_SIZE as u32;
+    let primitive_polynomial = PRIMITIVE_POLYNOMIALS[V1_BITS as usize - 3];
+    let mut logs = [LOG_UNDEFINED; BUILTIN_SIZE];
+    let mut exps = [0; BUILTIN_SIZE];
+    let mut x = 1;
+    let mut i = 0;
+    while i < size {
+        exps[i as usize] = x;
+        if logs[x as usize] == LOG_UNDEFINED {
+            logs[x as usize] = i
+        }
+        x <<= 1;
+        if x >= size {
+            x ^= primitive_polynomial;
+            x &= size - 1;
+        }
+        i += 1;
+    }
+    (logs, exps)
+}
+
+/// Generate a table of logarithms and exponents in `GF(2^bits)` for given
+/// `bits`, using caller-provided primitive polynomial.
+///
+/// `bits` must be checked elsewhere to be within the acceptable `BIT_RANGE`.
+/// If the polynomial is not primitive, some logarithms remain undefined.
+fn generate_logs_and_exps_with_polynomial(
+    bits: u32,
+    primitive_polynomial: u32,
+) -> (Vec<u32>, Vec<u32>) {
     let size = 2u32.pow(bits); // the number of elements in `GF(2^bits)`
 
-    let mut logs: Vec<Option<u32>> = Vec::with_capacity(size as usize);
-    for _i in 0..size {
-        logs.push(None)
-    } // 0th element could not be reached during the cycling and is undefined
+    // 0th element could not be reached during the cycling and remains undefined
+    let mut logs: Vec<u32> = vec![LOG_UNDEFINED; size as usize];
 
     let mut exps: Vec<u32> = Vec::with_capacity(size as usize);
 
     let mut x = 1;
-    let primitive_polynomial = primitive_polynomial(bits);
     for i in 0..size {
         exps.push(x);
-        if logs[x as usize].is_none() {
-            logs[x as usize] = Some(i)
+        if logs[x as usize] == LOG_UNDEFINED {
+            logs[x as usize] = i
         } // x = 1 is encountered twice
         x <<= 1; // left shift
         if x >= size {
@@ -535,7 +3574,24 @@ pub(crate) fn generate_logs_and_exps(bits: u32) -> (Vec<Option<u32>>, Vec<u32>)
 pub(crate) fn lagrange(
     x: &[u32],
     y: &[u32],
-    logs: &[Option<u32>],
+    logs: &[u32],
+    exps: &[u32],
+    bits: u32,
+) -> Result<u32, BananaError> {
+    lagrange_at(0, x, y, logs, exps, bits)
+}
+
+/// Calculate Lagrange interpolation polynomial in `GF(2^bits)` at point `at`.
+///
+/// Polynomial value at `0` is the secret component, polynomial value at
+/// non-zero `at` is the component of the share with id `at`.
+///
+/// Inputs are same as in [`lagrange`].
+fn lagrange_at(
+    at: u32,
+    x: &[u32],
+    y: &[u32],
+    logs: &[u32],
     exps: &[u32],
     bits: u32,
 ) -> Result<u32, BananaError> {
@@ -543,36 +3599,234 @@ pub(crate) fn lagrange(
     let size = 2u32.pow(bits);
     let len = x.len();
 
-    for i in 0..len {
+    'terms: for i in 0..len {
         match logs.get(y[i] as usize) {
-            Some(Some(a)) => {
+            // encountered the only undefined element (through Galois field properties), i.e. tried to calculate `log[0]`
+            Some(&LOG_UNDEFINED) => (),
+
+            Some(a) => {
                 let mut product = *a;
                 for j in 0..len {
                     if i != j {
-                        let p1 = match logs.get(x[j] as usize) {
-                            Some(a) => a.expect(
-                                "x[j] is never zero, it is share number, numbering starts from 1",
-                            ),
-                            None => return Err(BananaError::LogOutOfRange(x[j])),
+                        let p1 = match logs.get((at ^ x[j]) as usize) {
+                            // `at` is equal to `x[j]`, whole term is zero and is skipped;
+                            // for `at = 0` this never happens, since `x[j]` is share number, numbering starts from 1
+                            Some(&LOG_UNDEFINED) => continue 'terms,
+
+                            Some(a) => *a,
+
+                            None => return Err(BananaError::LogOutOfRange(at ^ x[j])),
                         };
-                        let p2 = match logs.get((x[i]^x[j]) as usize) {
-                            Some(a) => a.expect("x[i] and x[j] are never equal for non-equal i and j, through Galois field properties"),
-                            None => return Err(BananaError::LogOutOfRange(x[i]^x[j])),
+                        let p2 = match logs.get((x[i] ^ x[j]) as usize) {
+                            // x[i] and x[j] are never equal for non-equal i and j in a valid set,
+                            // through Galois field properties
+                            Some(&LOG_UNDEFINED) => {
+                                return Err(BananaError::FieldInvariantViolated {
+                                    context: "share ids are not unique",
+                                })
+                            }
+
+                            Some(a) => *a,
+
+                            None => return Err(BananaError::LogOutOfRange(x[i] ^ x[j])),
                         };
                         product = ((size - 1) + product + p1 - p2) % (size - 1);
                     }
                 }
 
                 // product is always positive and below `2^bits`, exponent is always addressed correctly
-                sum ^= exps[product as usize];
+                match exps.get(product as usize) {
+                    Some(a) => sum ^= a,
+                    None => return Err(BananaError::LogOutOfRange(product)),
+                }
             }
 
-            // encountered the only undefined element (through Galois field properties), i.e. tried to calculate `log[0]`
-            Some(None) => (),
-
             // this should not happen, but values of `y` elements are `u8` by decoding, and could in principle exceed `2^bits` number of elements in logs vector
             None => return Err(BananaError::LogOutOfRange(y[i])),
         }
     }
     Ok(sum)
 }
+
+/// Distinct result of combining share subsets, in
+/// [`SetInProgress::consistency_check`].
+struct SubsetResult {
+    /// Reconstructed bit sequence.
+    raw_bits: BitVec<u8, Msb0>,
+
+    /// Number of subsets producing this result.
+    subsets: usize,
+
+    /// Share positions in set used in the subsets producing this result.
+    covered: Vec<bool>,
+}
+
+impl Drop for SubsetResult {
+    fn drop(&mut self) {
+        self.raw_bits.as_raw_mut_slice().zeroize()
+    }
+}
+
+/// Advance `positions` to the next combination of positions below `total`,
+/// in lexicographic order.
+///
+/// Returns `false` if `positions` is already the last combination.
+fn next_combination(positions: &mut [usize], total: usize) -> bool {
+    let k = positions.len();
+    for i in (0..k).rev() {
+        if positions[i] < total - k + i {
+            positions[i] += 1;
+            for j in i + 1..k {
+                positions[j] = positions[j - 1] + 1;
+            }
+            return true;
+        }
+    }
+    false
+}
+
+/// Content of the share with id `target_id`, evaluated from contents of
+/// shares with `ids`.
+///
+/// `target_id` must be checked elsewhere to be a valid share id for `bits`.
+fn content_at(
+    target_id: u32,
+    ids: &[u32],
+    content_set: &[&[u8]],
+    tables: &FieldTables,
+    bits: u32,
+) -> Result<Vec<u8>, BananaError> {
+    // evaluate the polynomial for each content element
+    let content_length = content_set.first().map_or(0, |a| a.len());
+    let mut content = Vec::with_capacity(content_length);
+    let mut column: Vec<u32> = Vec::with_capacity(ids.len());
+    for i in 0..content_length {
+        column.clear();
+        column.extend(content_set.iter().map(|a| a[i] as u32));
+        let new = match lagrange_at(target_id, ids, &column, tables.logs(), tables.exps(), bits) {
+            Ok(a) => a,
+            Err(e) => {
+                column.zeroize();
+                content.zeroize();
+                return Err(e);
+            }
+        };
+
+        // content elements are `u8` by decoding, for bits above `8` the
+        // evaluated element could be out of that range
+        match u8::try_from(new) {
+            Ok(a) => content.push(a),
+            Err(_) => {
+                column.zeroize();
+                content.zeroize();
+                return Err(BananaError::DerivedShareUnrepresentable(target_id));
+            }
+        }
+    }
+    column.zeroize();
+    Ok(content)
+}
+
+/// Memory allocated for tables of logarithms and exponents in `GF(2^bits)`,
+/// in bytes.
+///
+/// Built-in tables for `bits = 8` need no allocation.
+fn tables_memory_bytes(bits: u32) -> usize {
+    if bits == V1_BITS {
+        0
+    } else {
+        2usize.pow(bits) * 2 * core::mem::size_of::<u32>()
+    }
+}
+
+/// Multiply two elements of `GF(2^bits)`, without tables.
+///
+/// Elements must be below `2^bits`.
+pub(crate) fn gf_mul(mut a: u32, mut b: u32, bits: u32, primitive_polynomial: u32) -> u32 {
+    let size = 2u32.pow(bits);
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 == 1 {
+            product ^= a;
+        }
+        b >>= 1;
+        a <<= 1;
+        if a >= size {
+            a ^= primitive_polynomial;
+            a &= size - 1;
+        }
+    }
+    product
+}
+
+/// Inverse of a non-zero element of `GF(2^bits)`, without tables.
+///
+/// Calculated as `a^(2^bits - 2)`.
+fn gf_inv(a: u32, bits: u32, primitive_polynomial: u32) -> u32 {
+    let mut power = 2u32.pow(bits) - 2;
+    let mut base = a;
+    let mut inverse = 1;
+    while power != 0 {
+        if power & 1 == 1 {
+            inverse = gf_mul(inverse, base, bits, primitive_polynomial);
+        }
+        base = gf_mul(base, base, bits, primitive_polynomial);
+        power >>= 1;
+    }
+    inverse
+}
+
+/// Calculate Lagrange interpolation polynomial in `GF(2^bits)` at `0`,
+/// without tables.
+///
+/// Inputs and errors are same as in [`lagrange`], except the tables.
+pub(crate) fn lagrange_without_tables(
+    x: &[u32],
+    y: &[u32],
+    bits: u32,
+    primitive_polynomial: u32,
+) -> Result<u32, BananaError> {
+    let mut sum = 0;
+    let size = 2u32.pow(bits);
+    let len = x.len();
+
+    for i in 0..len {
+        if y[i] >= size {
+            return Err(BananaError::LogOutOfRange(y[i]));
+        }
+
+        // zero element contributes nothing
+        if y[i] == 0 {
+            continue;
+        }
+
+        let mut numerator = y[i];
+        let mut denominator = 1;
+        for j in 0..len {
+            if i != j {
+                // share numbering starts from 1, so `x[j]` is never zero in a valid set
+                if x[j] >= size {
+                    return Err(BananaError::LogOutOfRange(x[j]));
+                }
+                let difference = x[i] ^ x[j];
+                if difference >= size {
+                    return Err(BananaError::LogOutOfRange(difference));
+                }
+                if difference == 0 {
+                    return Err(BananaError::FieldInvariantViolated {
+                        context: "share ids are not unique",
+                    });
+                }
+                numerator = gf_mul(numerator, x[j], bits, primitive_polynomial);
+                denominator = gf_mul(denominator, difference, bits, primitive_polynomial);
+            }
+        }
+        sum ^= gf_mul(
+            numerator,
+            gf_inv(denominator, bits, primitive_polynomial),
+            bits,
+            primitive_polynomial,
+        );
+    }
+    Ok(sum)
+}
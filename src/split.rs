@@ -0,0 +1,87 @@
+//! Splitting the secrets into shares.
+use alloc::vec::Vec;
+
+use bitvec::prelude::{BitVec, Msb0};
+use rand_core::{CryptoRng, RngCore};
+use xsalsa20poly1305::aead::{generic_array::GenericArray, Aead};
+use zeroize::Zeroize;
+
+use crate::error::BananaError;
+use crate::shares::{
+    cipher, gf_mul, max_distributable_shares, primitive_polynomial, title_salt, Share, V1_BITS,
+};
+
+/// Split the secret into version `V1` shares, as banana split does.
+///
+/// The secret is encrypted with the key derived from the passphrase, with
+/// random nonce. Encrypted data is split with Shamir secret sharing in
+/// `GF(2^8)`, so that any `required_shares` out of `total_shares` shares
+/// could be combined. Share ids are `1..=total_shares`.
+///
+/// Randomness for the nonce and the polynomial coefficients comes from the
+/// caller-provided cryptographically secure `rng`.
+///
+/// There must be at least two required shares, and at most `255` shares in
+/// total, with total number of shares not below the required one. Otherwise
+/// [`BananaError::SplitParametersInvalid`] is returned.
+pub fn split_secret(
+    title: &str,
+    secret: &str,
+    total_shares: usize,
+    required_shares: usize,
+    passphrase: &str,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Result<Vec<Share>, BananaError> {
+    let max = max_distributable_shares(V1_BITS)? as usize;
+    if required_shares < 2 || total_shares < required_shares || total_shares > max {
+        return Err(BananaError::SplitParametersInvalid {
+            total_shares,
+            required_shares,
+        });
+    }
+
+    // encrypt the secret
+    let mut nonce = [0u8; 24];
+    rng.fill_bytes(&mut nonce);
+    let encrypted = cipher(passphrase.as_bytes(), &title_salt(title))?
+        .encrypt(GenericArray::from_slice(&nonce), secret.as_bytes())
+        .expect("in-memory secret is always short enough for xsalsa20poly1305");
+
+    // padding marker followed by the encrypted data, padded from the left
+    // with zeroes to whole number of field elements; this is the inverse of
+    // what happens on combining the shares
+    let data_bits: BitVec<u8, Msb0> = BitVec::from_vec(encrypted);
+    let padding = (V1_BITS as usize - (data_bits.len() + 1) % V1_BITS as usize) % V1_BITS as usize;
+    let mut padded: BitVec<u8, Msb0> = BitVec::repeat(false, padding);
+    padded.push(true);
+    padded.extend_from_bitslice(&data_bits);
+
+    let polynomial = primitive_polynomial(V1_BITS);
+    let mut contents: Vec<Vec<u8>> =
+        vec![Vec::with_capacity(padded.len() / V1_BITS as usize); total_shares];
+    let mut coefficients = vec![0u8; required_shares];
+
+    // for bits `8` field elements are bytes, so padded data is read by bytes
+    for secret_element in padded.into_vec().into_iter() {
+        // polynomial with the secret element as the free term
+        coefficients[0] = secret_element;
+        rng.fill_bytes(&mut coefficients[1..]);
+
+        // evaluate the polynomial at each share id, with Horner's method
+        for (i, content) in contents.iter_mut().enumerate() {
+            let id = i as u32 + 1;
+            let mut value = 0;
+            for coefficient in coefficients.iter().rev() {
+                value = gf_mul(value, id, V1_BITS, polynomial) ^ *coefficient as u32;
+            }
+            content.push(value as u8);
+        }
+    }
+    coefficients.zeroize();
+
+    Ok(contents
+        .into_iter()
+        .enumerate()
+        .map(|(i, content)| Share::generated(title, required_shares, &nonce, i as u32 + 1, content))
+        .collect())
+}
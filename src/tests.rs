@@ -1,5 +1,23 @@
-use crate::shares::{generate_logs_and_exps, BIT_RANGE};
-use crate::{Share, ShareCollection};
+use alloc::{boxed::Box, string::String, vec::Vec};
+
+use scrypt::{scrypt, Params};
+use sha2::{Digest, Sha512};
+use xsalsa20poly1305::aead::{generic_array::GenericArray, Aead, KeyInit};
+use xsalsa20poly1305::XSalsa20Poly1305;
+
+#[cfg(feature = "generator")]
+use crate::passphrase::WORDLIST_LENGTH;
+use crate::passphrase::{self, CandidateOptions, PassphraseVariant};
+use crate::shares::{
+    generate_logs_and_exps, gf_mul, lagrange, lagrange_without_tables, primitive_polynomial,
+    FieldTables, SetInProgress, BIT_RANGE, LOG_UNDEFINED,
+};
+use crate::{
+    combine_collections, detect, split_secret, version_histogram, AddOutcome, BananaError,
+    BananaWarning, DetectedShare, ErrorKind, MultiShareCollection, ParseOptions, Progress,
+    ScryptParams, SecretKind, SecretSink, SetCombined, Share, ShareCollection, ShareMismatch,
+    Sufficiency, Version, DEFAULT_MAX_CONTENT_LENGTH, ERROR_CODES, SHARE_REJECTED_CODE_OFFSET,
+};
 
 const ALICE_SEEDPHRASE: &str =
     "bottom drive obey lake curtain smoke basket hold race lonely fit walk";
@@ -24,8 +42,8 @@ fn alice_recovers_secret1() {
     let mut share_collection = ShareCollection::new();
     let share1 = Share::new(hex::decode(SCAN_A1).unwrap()).unwrap();
     let share3 = Share::new(hex::decode(SCAN_A3).unwrap()).unwrap();
-    share_collection.add_share(share1).unwrap();
-    share_collection.add_share(share3).unwrap();
+    let _ = share_collection.add_share(share1).unwrap();
+    let _ = share_collection.add_share(share3).unwrap();
     if let ShareCollection::Ready(combined) = share_collection {
         let alice_secret = combined.recover_with_passphrase(PASSPHRASE_A).unwrap();
         assert_eq!(alice_secret, ALICE_SEEDPHRASE);
@@ -39,8 +57,8 @@ fn alice_recovers_secret2() {
     let mut share_collection = ShareCollection::new();
     let share2 = Share::new(hex::decode(SCAN_A2).unwrap()).unwrap();
     let share3 = Share::new(hex::decode(SCAN_A3).unwrap()).unwrap();
-    share_collection.add_share(share2).unwrap();
-    share_collection.add_share(share3).unwrap();
+    let _ = share_collection.add_share(share2).unwrap();
+    let _ = share_collection.add_share(share3).unwrap();
     if let ShareCollection::Ready(combined) = share_collection {
         let alice_secret = combined.recover_with_passphrase(PASSPHRASE_A).unwrap();
         assert_eq!(alice_secret, ALICE_SEEDPHRASE);
@@ -53,8 +71,8 @@ fn alice_recovers_secret2() {
 fn alice_makes_weird_title() {
     let maybe_share = Share::new(hex::decode(SCAN_B1).unwrap());
     assert!(maybe_share.is_ok(), "Should be parsed normally");
-    let title = maybe_share.unwrap().title();
-    assert_eq!(title, r#"terrible"truth\"escaping"#);
+    let share = maybe_share.unwrap();
+    assert_eq!(share.title(), r#"terrible"truth\"escaping"#);
 }
 
 #[test]
@@ -62,8 +80,8 @@ fn alice_recovers_secret3() {
     let mut share_collection = ShareCollection::new();
     let share1 = Share::new(hex::decode(SCAN_C1).unwrap()).unwrap();
     let share2 = Share::new(hex::decode(SCAN_C2).unwrap()).unwrap();
-    share_collection.add_share(share1).unwrap();
-    share_collection.add_share(share2).unwrap();
+    let _ = share_collection.add_share(share1).unwrap();
+    let _ = share_collection.add_share(share2).unwrap();
     if let ShareCollection::InProgress(ref in_progress) = share_collection {
         assert_eq!(in_progress.shares_now(), 2);
         assert_eq!(in_progress.shares_required(), 3);
@@ -73,7 +91,7 @@ fn alice_recovers_secret3() {
     }
 
     let share3 = Share::new(hex::decode(SCAN_C3).unwrap()).unwrap();
-    share_collection.add_share(share3).unwrap();
+    let _ = share_collection.add_share(share3).unwrap();
     if let ShareCollection::Ready(combined) = share_collection {
         let alice_secret = combined.recover_with_passphrase(PASSPHRASE_C).unwrap();
         assert_eq!(alice_secret, ALICE_SEEDPHRASE);
@@ -89,10 +107,3148 @@ fn math_works_as_expected() {
         let (logs, _) = generate_logs_and_exps(n);
         for (i, x) in logs.iter().enumerate() {
             if i == 0 {
-                assert!(x.is_none(), "log[0] should remain undefined")
+                assert_eq!(*x, LOG_UNDEFINED, "log[0] should remain undefined")
             } else {
-                assert!(x.is_some(), "log[i] should be determined for i != 0")
+                assert!(*x < (1 << n) - 1, "log[i] should be determined for i != 0")
             }
         }
     }
 }
+
+/// Key derived the way banana split does.
+fn secret_key(title: &str, passphrase: &str) -> [u8; 32] {
+    let mut hasher = Sha512::new();
+    hasher.update(title.as_bytes());
+    let salt = hasher.finalize();
+    let params = Params::new(15, 8, 1).unwrap();
+    let mut key = [0u8; 32];
+    scrypt(passphrase.as_bytes(), &salt, &params, &mut key).unwrap();
+    key
+}
+
+/// Encrypt `secret` the way banana split does.
+fn encrypt_with_key(key: &[u8; 32], nonce: &[u8], secret: &[u8]) -> Vec<u8> {
+    let cipher = XSalsa20Poly1305::new(GenericArray::from_slice(&key[..]));
+    cipher
+        .encrypt(GenericArray::from_slice(nonce), secret)
+        .unwrap()
+}
+
+/// Encrypt `secret` the way banana split does, and wrap the result into
+/// [`SetCombined`], bypassing the shares.
+fn combined_from_secret(title: &str, secret: &[u8], passphrase: &str) -> SetCombined {
+    let nonce = [7u8; 24];
+    let data = encrypt_with_key(&secret_key(title, passphrase), &nonce, secret);
+    SetCombined::from_parts(title, data, nonce.to_vec()).unwrap()
+}
+
+#[test]
+fn legacy_utf16le_secret_recovered() {
+    let secret: Vec<u8> = ALICE_SEEDPHRASE
+        .encode_utf16()
+        .flat_map(|x| x.to_le_bytes())
+        .collect();
+    let combined = combined_from_secret("legacy", &secret, PASSPHRASE_A);
+    assert_eq!(
+        combined.recover_utf16le(PASSPHRASE_A).unwrap(),
+        ALICE_SEEDPHRASE
+    );
+    // interleaved zero bytes are valid UTF-8, but not the expected text
+    assert_ne!(
+        combined.recover_with_passphrase(PASSPHRASE_A).unwrap(),
+        ALICE_SEEDPHRASE
+    );
+}
+
+#[test]
+fn legacy_utf16le_lone_surrogate_rejected() {
+    // 0xD800 is a high surrogate without a pair
+    let combined = combined_from_secret("legacy", &[0x61, 0x00, 0x00, 0xD8], PASSPHRASE_A);
+    assert!(matches!(
+        combined.recover_utf16le(PASSPHRASE_A),
+        Err(BananaError::DecodedSecretNotString)
+    ));
+}
+
+#[test]
+fn exhausted_set_below_threshold() {
+    let mut share_collection = ShareCollection::new();
+    let share1 = Share::new(hex::decode(SCAN_C1).unwrap()).unwrap();
+    let share2 = Share::new(hex::decode(SCAN_C2).unwrap()).unwrap();
+    let _ = share_collection.add_share(share1).unwrap();
+    let _ = share_collection.add_share(share2).unwrap();
+    if let ShareCollection::InProgress(ref mut in_progress) = share_collection {
+        assert!(!in_progress.is_exhausted());
+        assert!(matches!(
+            in_progress.declare_exhausted(),
+            Err(BananaError::ThresholdUnreachable { have: 2, need: 3 })
+        ));
+        assert!(in_progress.is_exhausted());
+        assert!((in_progress.redundancy() - 2.0 / 3.0).abs() < f32::EPSILON);
+        assert_eq!(in_progress.outstanding_required(&[1, 2, 3, 4, 5]), vec![3]);
+        assert_eq!(in_progress.outstanding_required(&[5, 5, 2, 4]), vec![5]);
+        assert!(in_progress.outstanding_required(&[1, 2]).is_empty());
+
+        // share found later revokes the declaration
+        in_progress
+            .add_share(Share::new(hex::decode(SCAN_C3).unwrap()).unwrap())
+            .unwrap();
+        assert!(!in_progress.is_exhausted());
+        in_progress.declare_exhausted().unwrap();
+    } else {
+        panic!("Two shares are not enough.")
+    }
+}
+
+#[test]
+fn versions_counted() {
+    // share with no version in json
+    let json = r#"{"t":"old","r":2,"d":"801aabbcc","n":"wLJ+K1f4VeIUxJjpQsolrHdrZIdZaeqv"}"#;
+    let shares = vec![
+        Share::new(hex::decode(SCAN_A1).unwrap()).unwrap(),
+        Share::new(hex::decode(SCAN_B1).unwrap()).unwrap(),
+        Share::new(json.as_bytes().to_vec()).unwrap(),
+    ];
+    let histogram = version_histogram(&shares);
+    assert_eq!(histogram.len(), 2);
+    assert_eq!(histogram[&Version::V1], 2);
+    assert_eq!(histogram[&Version::Undefined], 1);
+}
+
+#[test]
+fn damaged_nonce_rejected_at_parse() {
+    let json = r#"{"v":1,"t":"damaged","r":2,"d":"8AQID","n":"not base64!"}"#;
+    assert!(matches!(
+        Share::new(json.as_bytes().to_vec()),
+        Err(BananaError::NonceNotBase64)
+    ));
+}
+
+struct TestSink(Vec<u8>);
+
+impl SecretSink for TestSink {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes)
+    }
+}
+
+#[test]
+fn secret_into_sink() {
+    let mut share_collection = ShareCollection::new();
+    let share1 = Share::new(hex::decode(SCAN_A1).unwrap()).unwrap();
+    let share2 = Share::new(hex::decode(SCAN_A2).unwrap()).unwrap();
+    let _ = share_collection.add_share(share1).unwrap();
+    let _ = share_collection.add_share(share2).unwrap();
+    if let ShareCollection::Ready(combined) = share_collection {
+        let mut sink = TestSink(Vec::new());
+        combined.recover_into_sink(PASSPHRASE_A, &mut sink).unwrap();
+        assert_eq!(sink.0, ALICE_SEEDPHRASE.as_bytes());
+
+        let mut sink = TestSink(Vec::new());
+        assert!(matches!(
+            combined.recover_into_sink(PASSPHRASE_C, &mut sink),
+            Err(BananaError::DecodingFailed)
+        ));
+        assert!(sink.0.is_empty());
+    } else {
+        panic!("Two different shares are sufficient.")
+    }
+}
+
+const TITLE_CRAFTED: &str = "crafted";
+const NONCE_CRAFTED: &str = "wLJ+K1f4VeIUxJjpQsolrHdrZIdZaeqv";
+
+/// V1 share with given id and content, and fixed title and nonce.
+///
+/// Note that two shares with identical content combine exactly into that
+/// content, whatever the share ids are.
+fn share_with_content(id: u8, content: &[u8], required_shares: usize) -> Share {
+    let body = [vec![id], content.to_vec()].concat();
+    let json = crafted_json(
+        required_shares,
+        &format!("8{}", base64::encode(body)),
+        NONCE_CRAFTED,
+    );
+    Share::new(json.into_bytes()).unwrap()
+}
+
+/// Json of crafted share, with share body `d` and nonce `n` as given.
+fn crafted_json(required_shares: usize, body: &str, nonce: &str) -> String {
+    format!(
+        r#"{{"v":1,"t":"{}","r":{},"d":"{}","n":"{}"}}"#,
+        TITLE_CRAFTED, required_shares, body, nonce,
+    )
+}
+
+#[test]
+fn padding_marker_checked() {
+    for (content, is_valid) in [
+        ([vec![0x00, 0x01], vec![0xab; 16]].concat(), true),
+        (vec![0x02, 0xab], false),
+        (vec![0x00, 0x00], false),
+    ] {
+        let mut share_collection = ShareCollection::new();
+        let _ = share_collection
+            .add_share_lenient(share_with_content(1, &content, 2))
+            .unwrap();
+        let result = share_collection.add_share_lenient(share_with_content(2, &content, 2));
+        if is_valid {
+            let _ = result.unwrap();
+            assert!(matches!(share_collection, ShareCollection::Ready(_)));
+        } else {
+            assert!(matches!(result, Err(BananaError::MalformedPadding)));
+        }
+    }
+}
+
+#[test]
+fn malformed_combined_data_detected() {
+    // shares of polynomial `f(x) = x`, combined value `f(0)` is all zeros
+    let mut share_collection = ShareCollection::new();
+    let _ = share_collection
+        .add_share(share_with_content(1, &[0x01; 4], 2))
+        .unwrap();
+    assert!(matches!(
+        share_collection.add_share(share_with_content(2, &[0x02; 4], 2)),
+        Err(BananaError::MalformedPadding)
+    ));
+
+    // padding marker is there, but the data is shorter than the tag
+    let content = [0x00, 0x01, 0xab];
+    let mut share_collection = ShareCollection::new();
+    let _ = share_collection
+        .add_share(share_with_content(1, &content, 2))
+        .unwrap();
+    assert!(matches!(
+        share_collection.add_share_lenient(share_with_content(2, &content, 2)),
+        Err(BananaError::CombinedDataMalformed(1))
+    ));
+}
+
+#[test]
+fn session_tries_passphrases() {
+    let mut share_collection = ShareCollection::new();
+    let share2 = Share::new(hex::decode(SCAN_A2).unwrap()).unwrap();
+    let share3 = Share::new(hex::decode(SCAN_A3).unwrap()).unwrap();
+    let _ = share_collection.add_share(share2).unwrap();
+    let _ = share_collection.add_share(share3).unwrap();
+    if let ShareCollection::Ready(combined) = share_collection {
+        assert_eq!(combined.nonce().len(), 24);
+        assert_eq!(combined.nonce_base64(), "o9DbpBi9r7UWJHOriuDArR4Vrc0VOo3l");
+        let session = combined.session();
+        assert!(matches!(
+            session.try_passphrase(PASSPHRASE_C),
+            Err(BananaError::DecodingFailed)
+        ));
+        assert_eq!(
+            session.try_passphrase(PASSPHRASE_A).unwrap(),
+            ALICE_SEEDPHRASE
+        );
+    } else {
+        panic!("Two different shares are sufficient.")
+    }
+}
+
+#[test]
+fn share_detected() {
+    assert_eq!(
+        detect(&hex::decode(SCAN_C1).unwrap()),
+        Some(DetectedShare {
+            version: Version::V1,
+            title: String::from("alice has too many friends"),
+            required_shares: 3,
+        })
+    );
+    assert_eq!(detect(b"https://example.com"), None);
+    assert_eq!(detect(br#"{"t":"not a share"}"#), None);
+    assert_eq!(detect(&[0xff, 0xfe]), None);
+    assert_eq!(
+        detect(br#"{"v":2,"t":"future","r":2,"d":"8AQID","n":"wLJ+K1f4VeIUxJjpQsolrHdrZIdZaeqv"}"#),
+        None
+    );
+}
+
+#[test]
+fn leading_zero_bytes_survive_combine() {
+    // looking for a secret starting with zero bytes, with encrypted data also
+    // starting with zero byte
+    let key = secret_key(TITLE_CRAFTED, PASSPHRASE_A);
+    let nonce = base64::decode(NONCE_CRAFTED).unwrap();
+    let (secret, data) = (0..128u8)
+        .flat_map(|i| (0..128u8).map(move |j| vec![0, 0, i, j]))
+        .map(|secret| {
+            let data = encrypt_with_key(&key, &nonce, &secret);
+            (secret, data)
+        })
+        .find(|(_, data)| data[0] == 0)
+        .unwrap();
+
+    // padding marker byte goes first
+    let content = [vec![0x01], data].concat();
+    let mut share_collection = ShareCollection::new();
+    let _ = share_collection
+        .add_share_lenient(share_with_content(1, &content, 2))
+        .unwrap();
+    let _ = share_collection
+        .add_share_lenient(share_with_content(2, &content, 2))
+        .unwrap();
+    if let ShareCollection::Ready(combined) = share_collection {
+        let recovered = combined.recover_with_passphrase(PASSPHRASE_A).unwrap();
+        assert_eq!(recovered.as_bytes(), secret);
+    } else {
+        panic!("Two different shares are sufficient.")
+    }
+}
+
+#[test]
+fn raw_bits_before_padding_processing() {
+    let content = vec![0x00, 0x01, 0xab];
+    let mut share_collection = ShareCollection::new();
+    let _ = share_collection
+        .add_share_lenient(share_with_content(1, &content, 3))
+        .unwrap();
+    let _ = share_collection
+        .add_share_lenient(share_with_content(2, &content, 3))
+        .unwrap();
+    if let ShareCollection::InProgress(ref mut in_progress) = share_collection {
+        let raw_bits = in_progress.combine_raw_bits().unwrap();
+        assert_eq!(raw_bits.first_one(), Some(15));
+        assert_eq!(raw_bits.into_vec(), content);
+    } else {
+        panic!("Two shares are not enough.")
+    }
+}
+
+/// Multiplication in `GF(2^8)` with given primitive polynomial.
+fn gf256_mul(mut a: u8, mut b: u8, polynomial: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 == 1 {
+            product ^= a;
+        }
+        let carry = a & 0x80 != 0;
+        a <<= 1;
+        if carry {
+            a ^= polynomial;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+#[test]
+fn combine_with_alternate_polynomial() {
+    // x^8 + x^5 + x^3 + x + 1 is primitive, built-in table has
+    // x^8 + x^4 + x^3 + x^2 + 1 (29) instead
+    let alternate = 43u8;
+    let mut polynomials = [0u32; 18];
+    polynomials[8 - 3] = alternate as u32;
+
+    let key = secret_key(TITLE_CRAFTED, PASSPHRASE_A);
+    let nonce = base64::decode(NONCE_CRAFTED).unwrap();
+    let data = encrypt_with_key(&key, &nonce, ALICE_SEEDPHRASE.as_bytes());
+
+    // linear polynomial per byte, constant term is padding marker byte and
+    // encrypted data
+    let free = [vec![0x01], data].concat();
+    let linear: Vec<u8> = (0..free.len()).map(|i| (i * 37 + 11) as u8).collect();
+    let shares: Vec<Share> = (1..=2u8)
+        .map(|id| {
+            let content: Vec<u8> = free
+                .iter()
+                .zip(linear.iter())
+                .map(|(a0, a1)| a0 ^ gf256_mul(*a1, id, alternate))
+                .collect();
+            share_with_content(id, &content, 2)
+        })
+        .collect();
+
+    let mut shares = shares.into_iter();
+    let mut set = SetInProgress::init(shares.next().unwrap());
+    set.add_share(shares.next().unwrap()).unwrap();
+
+    let combined = set.combine_with_polynomials(&polynomials).unwrap();
+    assert_eq!(
+        combined.recover_with_passphrase(PASSPHRASE_A).unwrap(),
+        ALICE_SEEDPHRASE
+    );
+
+    // AES polynomial x^8 + x^4 + x^3 + x + 1 is irreducible, but not primitive
+    polynomials[8 - 3] = 27;
+    assert!(matches!(
+        set.combine_with_polynomials(&polynomials),
+        Err(BananaError::PolynomialNotPrimitive(27))
+    ));
+    assert!(matches!(
+        set.combine_with_polynomials(&polynomials[..5]),
+        Err(BananaError::PolynomialMissing(8))
+    ));
+}
+
+#[test]
+fn unusual_bits_for_v1_warned() {
+    let share = Share::new(hex::decode(SCAN_A1).unwrap()).unwrap();
+    assert!(share.warnings().is_empty());
+
+    // bits `k` is 20, share id takes 3 bytes
+    let json =
+        r#"{"v":1,"t":"unusual","r":2,"d":"kAAABAgM=","n":"wLJ+K1f4VeIUxJjpQsolrHdrZIdZaeqv"}"#;
+    let share = Share::new(json.as_bytes().to_vec()).unwrap();
+    assert_eq!(
+        share.warnings(),
+        vec![BananaWarning::UnusualBitsForVersion {
+            version: Version::V1,
+            bits: 20
+        }]
+    );
+    assert_eq!(
+        format!("{}", share.warnings()[0]),
+        "Bits value 20 is unusual for share version v1. Likely the share is damaged."
+    );
+
+    // no such expectations for `Undefined` version
+    let json = r#"{"t":"unusual","r":2,"d":"k00000102","n":"wLJ+K1f4VeIUxJjpQsolrHdrZIdZaeqv"}"#;
+    let share = Share::new(json.as_bytes().to_vec()).unwrap();
+    assert!(share.warnings().is_empty());
+}
+
+#[test]
+fn recovery_verified() {
+    let mut share_collection = ShareCollection::new();
+    let share1 = Share::new(hex::decode(SCAN_A1).unwrap()).unwrap();
+    let share3 = Share::new(hex::decode(SCAN_A3).unwrap()).unwrap();
+    let _ = share_collection.add_share(share1).unwrap();
+    let _ = share_collection.add_share(share3).unwrap();
+    if let ShareCollection::Ready(combined) = share_collection {
+        assert_eq!(
+            combined.recover_verified(PASSPHRASE_A).unwrap(),
+            ALICE_SEEDPHRASE
+        );
+        assert!(matches!(
+            combined.recover_verified(PASSPHRASE_C),
+            Err(BananaError::DecodingFailed)
+        ));
+    } else {
+        panic!("Two different shares are sufficient.")
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn archive_parsed_line_by_line() {
+    let archive = format!(
+        "{}\n\n{}\r\nnot a share\n{}",
+        String::from_utf8(hex::decode(SCAN_C1).unwrap()).unwrap(),
+        String::from_utf8(hex::decode(SCAN_C2).unwrap()).unwrap(),
+        String::from_utf8(hex::decode(SCAN_C3).unwrap()).unwrap(),
+    );
+    let mut progress = Vec::new();
+    let results = crate::parse_archive(archive.as_bytes(), |line| progress.push(line));
+    assert_eq!(progress, vec![1, 2, 3, 4, 5]);
+    assert_eq!(
+        results
+            .iter()
+            .map(|(line, _)| *line)
+            .collect::<Vec<usize>>(),
+        vec![1, 3, 4, 5]
+    );
+    assert!(matches!(results[2].1, Err(BananaError::JsonParsing(_))));
+
+    let mut share_collection = ShareCollection::new();
+    for (_, result) in results {
+        if let Ok(share) = result {
+            let _ = share_collection.add_share(share).unwrap();
+        }
+    }
+    if let ShareCollection::Ready(combined) = share_collection {
+        let alice_secret = combined.recover_with_passphrase(PASSPHRASE_C).unwrap();
+        assert_eq!(alice_secret, ALICE_SEEDPHRASE);
+    } else {
+        panic!("Three different shares are sufficient.")
+    }
+}
+
+#[cfg(feature = "argon2")]
+#[test]
+fn argon2_fork_secret_recovered() {
+    // light parameters, to keep the test fast
+    let params = argon2::Params::new(256, 1, 1, Some(32)).unwrap();
+    let mut hasher = Sha512::new();
+    hasher.update(TITLE_CRAFTED.as_bytes());
+    let salt = hasher.finalize();
+    let mut key = [0u8; 32];
+    argon2::Argon2::new(
+        argon2::Algorithm::Argon2id,
+        argon2::Version::V0x13,
+        params.clone(),
+    )
+    .hash_password_into(PASSPHRASE_A.as_bytes(), &salt, &mut key)
+    .unwrap();
+    let nonce = [7u8; 24];
+    let data = encrypt_with_key(&key, &nonce, ALICE_SEEDPHRASE.as_bytes());
+    let combined = SetCombined::from_parts(TITLE_CRAFTED, data, nonce.to_vec()).unwrap();
+    assert_eq!(
+        combined
+            .recover_with_argon2(PASSPHRASE_A, params.clone())
+            .unwrap(),
+        ALICE_SEEDPHRASE
+    );
+    assert!(matches!(
+        combined.recover_with_argon2(PASSPHRASE_C, params),
+        Err(BananaError::DecodingFailed)
+    ));
+}
+
+#[test]
+fn vectors_verified() {
+    let vector_a = (
+        vec![hex::decode(SCAN_A1).unwrap(), hex::decode(SCAN_A3).unwrap()],
+        PASSPHRASE_A,
+        ALICE_SEEDPHRASE,
+    );
+    let vector_c = (
+        vec![
+            hex::decode(SCAN_C1).unwrap(),
+            hex::decode(SCAN_C2).unwrap(),
+            hex::decode(SCAN_C3).unwrap(),
+        ],
+        PASSPHRASE_C,
+        ALICE_SEEDPHRASE,
+    );
+    crate::verify_vectors(&[vector_a.clone(), vector_c.clone()]).unwrap();
+
+    let vector_wrong = (vector_a.0.clone(), PASSPHRASE_A, "wrong secret");
+    assert!(matches!(
+        crate::verify_vectors(&[vector_c.clone(), vector_wrong]),
+        Err(BananaError::VectorMismatch(1))
+    ));
+
+    let vector_short = (
+        vec![hex::decode(SCAN_C1).unwrap()],
+        PASSPHRASE_C,
+        ALICE_SEEDPHRASE,
+    );
+    assert!(matches!(
+        crate::verify_vectors(&[vector_short]),
+        Err(BananaError::ThresholdUnreachable { have: 1, need: 3 })
+    ));
+}
+
+#[test]
+fn shares_compared() {
+    let share_a1 = Share::new(hex::decode(SCAN_A1).unwrap()).unwrap();
+    let share_a1_again = Share::new(hex::decode(SCAN_A1).unwrap()).unwrap();
+    assert!(share_a1.diff(&share_a1_again).is_identical());
+
+    let share_a2 = Share::new(hex::decode(SCAN_A2).unwrap()).unwrap();
+    let diff = share_a1.diff(&share_a2);
+    assert!(!diff.is_identical());
+    assert!(diff.id);
+    assert_eq!(diff.content_offset, Some(0));
+    assert!(!(diff.version || diff.title || diff.required_shares || diff.nonce || diff.bits));
+
+    let share_c1 = Share::new(hex::decode(SCAN_C1).unwrap()).unwrap();
+    let diff = share_a1.diff(&share_c1);
+    assert!(diff.title && diff.required_shares && diff.nonce);
+    assert!(!(diff.version || diff.bits || diff.id));
+
+    // same share, truncated content
+    let content = vec![0x01, 0x02, 0x03];
+    let diff = share_with_content(1, &content, 2).diff(&share_with_content(1, &content[..2], 2));
+    assert_eq!(diff.content_offset, Some(2));
+}
+
+#[test]
+fn repeated_scans_deduplicated() {
+    let mut deduplicator = crate::ScanDeduplicator::<2>::new();
+    let scan_a1 = hex::decode(SCAN_A1).unwrap();
+    let scan_a2 = hex::decode(SCAN_A2).unwrap();
+    let scan_a3 = hex::decode(SCAN_A3).unwrap();
+    assert!(deduplicator.is_new(&scan_a1));
+    assert!(!deduplicator.is_new(&scan_a1));
+    assert!(deduplicator.is_new(&scan_a2));
+    assert!(!deduplicator.is_new(&scan_a1));
+
+    // ring capacity exceeded, oldest payload is forgotten
+    assert!(deduplicator.is_new(&scan_a3));
+    assert!(deduplicator.is_new(&scan_a1));
+    assert!(!deduplicator.is_new(&scan_a3));
+
+    deduplicator.clear();
+    assert!(deduplicator.is_new(&scan_a3));
+
+    let mut no_memory = crate::ScanDeduplicator::<0>::new();
+    assert!(no_memory.is_new(&scan_a1));
+    assert!(no_memory.is_new(&scan_a1));
+}
+
+#[test]
+fn lost_share_regenerated() {
+    let mut share_collection = ShareCollection::new();
+    for scan in [SCAN_C1, SCAN_C2, SCAN_C3] {
+        let _ = share_collection
+            .add_share(Share::new(hex::decode(scan).unwrap()).unwrap())
+            .unwrap();
+    }
+    let combined = if let ShareCollection::Ready(combined) = share_collection {
+        combined
+    } else {
+        panic!("Three different shares are sufficient.")
+    };
+
+    // existing share is not regenerated
+    assert!(matches!(
+        combined.regenerate_share(PASSPHRASE_C, 3, &[1, 2, 3]),
+        Err(BananaError::ShareAlreadyInSet)
+    ));
+    assert!(matches!(
+        combined.regenerate_share(PASSPHRASE_C, 3, &[1, 2]),
+        Err(BananaError::ShareAlreadyInSet)
+    ));
+
+    // new share works with the existing ones
+    let share4 = combined
+        .regenerate_share(PASSPHRASE_C, 4, &[3, 1, 2])
+        .unwrap();
+    let mut share_collection = ShareCollection::new();
+    let _ = share_collection
+        .add_share(Share::new(hex::decode(SCAN_C2).unwrap()).unwrap())
+        .unwrap();
+    let _ = share_collection.add_share(share4).unwrap();
+    let _ = share_collection
+        .add_share(Share::new(hex::decode(SCAN_C1).unwrap()).unwrap())
+        .unwrap();
+    if let ShareCollection::Ready(combined) = share_collection {
+        let alice_secret = combined.recover_with_passphrase(PASSPHRASE_C).unwrap();
+        assert_eq!(alice_secret, ALICE_SEEDPHRASE);
+    } else {
+        panic!("Three different shares are sufficient.")
+    }
+
+    assert!(matches!(
+        combined.regenerate_share(PASSPHRASE_C, 4, &[1, 2]),
+        Err(BananaError::ThresholdUnreachable { have: 2, need: 3 })
+    ));
+    assert!(matches!(
+        combined.regenerate_share(PASSPHRASE_C, 4, &[1, 2, 1]),
+        Err(BananaError::ShareAlreadyInSet)
+    ));
+    assert!(matches!(
+        combined.regenerate_share(PASSPHRASE_C, 4, &[1, 2, 5]),
+        Err(BananaError::ShareIdNotInSet(5))
+    ));
+    assert!(matches!(
+        combined.regenerate_share(PASSPHRASE_C, 256, &[1, 2, 3]),
+        Err(BananaError::ShareIdInvalid(256))
+    ));
+    assert!(matches!(
+        combined.regenerate_share(PASSPHRASE_A, 4, &[1, 2, 3]),
+        Err(BananaError::DecodingFailed)
+    ));
+}
+
+/// Shares 1 and 2 with bits `9`, for Alice seed phrase with `PASSPHRASE_A`.
+///
+/// Linear polynomial for each element is picked so that its values at `1`,
+/// `2` and `4` fit in bytes.
+fn bits9_shares() -> [Share; 2] {
+    let key = secret_key(TITLE_CRAFTED, PASSPHRASE_A);
+    let nonce = base64::decode(NONCE_CRAFTED).unwrap();
+    let data = encrypt_with_key(&key, &nonce, ALICE_SEEDPHRASE.as_bytes());
+
+    // padding zeros, padding marker and data, as elements of 9 bits
+    let padding = (9 - (data.len() * 8 + 1) % 9) % 9;
+    let mut bits = vec![false; padding];
+    bits.push(true);
+    bits.extend(
+        data.iter()
+            .flat_map(|byte| (0..8).rev().map(move |k| (byte >> k) & 1 == 1)),
+    );
+
+    let polynomial = primitive_polynomial(9);
+    let mut contents = [Vec::new(), Vec::new()];
+    for chunk in bits.chunks(9) {
+        let a0 = chunk.iter().fold(0, |acc, bit| (acc << 1) | *bit as u32);
+        let a1 = (0..512)
+            .find(|a1| {
+                [1, 2, 4]
+                    .iter()
+                    .all(|x| a0 ^ gf_mul(*a1, *x, 9, polynomial) < 256)
+            })
+            .unwrap();
+        for (content, x) in contents.iter_mut().zip(1..) {
+            content.push((a0 ^ gf_mul(a1, x, 9, polynomial)) as u8);
+        }
+    }
+    [1, 2].map(|id| share_with_bits9_content(id, &contents[id as usize - 1], 2))
+}
+
+#[test]
+fn lost_share_regenerated_for_bits_above_8() {
+    let [share1, share2] = bits9_shares();
+    let combined = ShareCollection::from_shares([share1.clone(), share2])
+        .unwrap()
+        .take_ready()
+        .unwrap();
+
+    // regenerated share works with the existing one
+    let share4 = combined.regenerate_share(PASSPHRASE_A, 4, &[1, 2]).unwrap();
+    let combined4 = ShareCollection::from_shares([share4, share1])
+        .unwrap()
+        .take_ready()
+        .unwrap();
+    assert_eq!(
+        combined4.recover_with_passphrase(PASSPHRASE_A).unwrap(),
+        ALICE_SEEDPHRASE
+    );
+
+    // values at `3` have same high bit as the secret elements
+    assert!(matches!(
+        combined.regenerate_share(PASSPHRASE_A, 3, &[1, 2]),
+        Err(BananaError::DerivedShareUnrepresentable(3))
+    ));
+}
+
+#[test]
+fn combined_from_share_iter() {
+    let shares = || {
+        [SCAN_C1, SCAN_C2, SCAN_C3]
+            .into_iter()
+            .map(|scan| Share::new(hex::decode(scan).unwrap()))
+            .chain(core::iter::once(Err(BananaError::EmptyShare)))
+    };
+
+    let combined = SetCombined::from_share_iter(shares(), true).unwrap();
+    let alice_secret = combined.recover_with_passphrase(PASSPHRASE_C).unwrap();
+    assert_eq!(alice_secret, ALICE_SEEDPHRASE);
+
+    // iterator exhausted, and the error is encountered
+    assert!(matches!(
+        SetCombined::from_share_iter(shares(), false),
+        Err(BananaError::EmptyShare)
+    ));
+
+    assert!(matches!(
+        SetCombined::from_share_iter(shares().take(2), false),
+        Err(BananaError::InsufficientShares { have: 2, need: 3 })
+    ));
+}
+
+#[test]
+fn scrypt_memory_calculated() {
+    assert_eq!(
+        crate::scrypt_memory_bytes(&Params::new(15, 8, 1).unwrap()),
+        32 * 1024 * 1024
+    );
+    assert_eq!(
+        crate::scrypt_memory_bytes(&Params::new(14, 8, 4).unwrap()),
+        16 * 1024 * 1024
+    );
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn compressed_secret_recovered() {
+    // Alice seed phrase compressed with python `gzip.compress(_, mtime=0)`
+    // and `zlib.compress`
+    const ALICE_GZIP: &str = "1f8b08000000000002030dc7d10dc0200805c055de6aa034125012a46ddcbebdbfe3a88a899efa0882e5c0c904edce225dd833fe316d93c208ef486a028f257e7069e125b70f91ee729c45000000";
+    const ALICE_ZLIB: &str = "789c0dc7d10dc0200805c055de6aa034125012a46ddcbebdbfe3a88a899efa0882e5c0c904edce225dd833fe316d93c208ef486a028f257e7069e125b70f8a2419b2";
+    for compressed in [ALICE_GZIP, ALICE_ZLIB] {
+        let combined = combined_from_secret(
+            "compressed",
+            &hex::decode(compressed).unwrap(),
+            PASSPHRASE_A,
+        );
+        assert_eq!(
+            combined.recover_decompressed(PASSPHRASE_A).unwrap(),
+            ALICE_SEEDPHRASE
+        );
+    }
+
+    // damaged gzip checksum
+    let mut damaged = hex::decode(ALICE_GZIP).unwrap();
+    let len = damaged.len();
+    damaged[len - 8] ^= 1;
+    let combined = combined_from_secret("compressed", &damaged, PASSPHRASE_A);
+    assert!(matches!(
+        combined.recover_decompressed(PASSPHRASE_A),
+        Err(BananaError::DecompressionFailed)
+    ));
+
+    // not compressed at all
+    let combined = combined_from_secret("compressed", ALICE_SEEDPHRASE.as_bytes(), PASSPHRASE_A);
+    assert!(matches!(
+        combined.recover_decompressed(PASSPHRASE_A),
+        Err(BananaError::DecompressionFailed)
+    ));
+}
+
+#[test]
+fn duplicate_ids_in_lagrange_not_panicking() {
+    let (logs, exps) = generate_logs_and_exps(8);
+    assert!(matches!(
+        lagrange(&[1, 1], &[10, 20], &logs, &exps, 8),
+        Err(BananaError::FieldInvariantViolated { .. })
+    ));
+
+    // zero id does not panic either
+    assert!(lagrange(&[0, 1], &[10, 20], &logs, &exps, 8).is_ok());
+}
+
+#[test]
+fn recovered_in_one_call() {
+    let payloads = vec![hex::decode(SCAN_C3).unwrap(), hex::decode(SCAN_C1).unwrap()];
+    assert!(matches!(
+        crate::recover(&payloads, PASSPHRASE_C),
+        Err(BananaError::InsufficientShares { have: 2, need: 3 })
+    ));
+    assert!(matches!(
+        crate::recover(&[], PASSPHRASE_C),
+        Err(BananaError::NoShares)
+    ));
+}
+
+#[test]
+fn share_json_restored() {
+    for scan in [
+        SCAN_A1, SCAN_A2, SCAN_A3, SCAN_B1, SCAN_C1, SCAN_C2, SCAN_C3,
+    ] {
+        let share = Share::new(hex::decode(scan).unwrap()).unwrap();
+        assert_eq!(share.to_qr_payload(), hex::decode(scan).unwrap());
+        assert_eq!(share.to_json().into_bytes(), hex::decode(scan).unwrap());
+        assert_eq!(Share::new(share.to_qr_payload()).unwrap(), share);
+    }
+
+    // legacy share, with hex body and no version
+    let json = r#"{"t":"old","r":2,"d":"801aabbcc","n":"wLJ+K1f4VeIUxJjpQsolrHdrZIdZaeqv"}"#;
+    let share = Share::new(json.as_bytes().to_vec()).unwrap();
+    assert_eq!(share.to_json(), json);
+    assert_eq!(Share::new(share.to_qr_payload()).unwrap(), share);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn shares_deduplicated_by_hash() {
+    let mut scans = std::collections::HashSet::new();
+    for _ in 0..3 {
+        for scan in [SCAN_A1, SCAN_A2] {
+            let _ = scans.insert(Share::new(hex::decode(scan).unwrap()).unwrap());
+        }
+    }
+    assert_eq!(scans.len(), 2);
+    assert!(scans.contains(&Share::from_hex_payload(SCAN_A1).unwrap()));
+
+    // content with a single bit flipped, same id
+    let share = share_with_content(1, b"Content", 2);
+    let damaged = share_with_content(1, b"Contenu", 2);
+    assert_ne!(share, damaged);
+    assert_eq!(share, share_with_content(1, b"Content", 2));
+    assert!(scans.insert(share));
+    assert!(scans.insert(damaged));
+    assert!(!scans.insert(share_with_content(1, b"Content", 2)));
+    assert_ne!(
+        share_with_content(1, b"Content", 2),
+        share_with_content(2, b"Content", 2)
+    );
+}
+
+#[test]
+fn collection_cloned() {
+    let mut share_collection = ShareCollection::new();
+    let _ = share_collection
+        .add_share(Share::from_hex_payload(SCAN_A1).unwrap())
+        .unwrap();
+    let earlier = share_collection.clone();
+
+    let _ = share_collection
+        .add_share(Share::from_hex_payload(SCAN_A2).unwrap())
+        .unwrap();
+    assert!(share_collection.is_ready());
+    assert_eq!(share_collection.ids(), &[1, 2]);
+
+    // clone remains in progress
+    assert!(!earlier.is_ready());
+    assert_eq!(earlier.ids(), &[1]);
+    let in_progress = earlier.in_progress().unwrap().clone();
+    assert_eq!(in_progress.shares_missing(), 1);
+
+    // and could be advanced separately
+    let mut earlier = earlier;
+    let _ = earlier
+        .add_share(Share::from_hex_payload(SCAN_A3).unwrap())
+        .unwrap();
+    assert_eq!(earlier.ids(), &[1, 3]);
+    let combined = earlier.take_ready().unwrap().clone();
+    assert_eq!(
+        combined.recover_with_passphrase(PASSPHRASE_A).unwrap(),
+        ALICE_SEEDPHRASE
+    );
+    assert_eq!(in_progress.ids(), &[1]);
+}
+
+#[test]
+fn encrypted_archive_round_trip() {
+    let mut in_progress = SetInProgress::init(Share::new(hex::decode(SCAN_C1).unwrap()).unwrap());
+    in_progress
+        .add_share(Share::new(hex::decode(SCAN_C3).unwrap()).unwrap())
+        .unwrap();
+    let archive = in_progress
+        .to_encrypted_archive("archive passphrase", &mut TestRng(0x5eed))
+        .unwrap();
+
+    assert!(matches!(
+        SetInProgress::from_encrypted_archive(&archive, "wrong passphrase"),
+        Err(BananaError::DecodingFailed)
+    ));
+    assert!(matches!(
+        SetInProgress::from_encrypted_archive(&archive[..40], "archive passphrase"),
+        Err(BananaError::DecodingFailed)
+    ));
+
+    let restored = SetInProgress::from_encrypted_archive(&archive, "archive passphrase").unwrap();
+    assert_eq!(restored.shares_now(), 2);
+    assert_eq!(restored.title(), in_progress.title());
+    assert_eq!(restored.bits(), 8);
+    assert_eq!(restored.nonce(), in_progress.nonce());
+    assert_eq!(restored.nonce_base64(), "wLJ+K1f4VeIUxJjpQsolrHdrZIdZaeqv");
+
+    // salt and nonce are fresh for each export
+    let exported = restored
+        .to_encrypted_archive("archive passphrase", &mut TestRng(0x5eee))
+        .unwrap();
+    assert_ne!(exported[..56], archive[..56]);
+    assert_eq!(
+        SetInProgress::from_encrypted_archive(&exported, "archive passphrase")
+            .unwrap()
+            .ids(),
+        in_progress.ids()
+    );
+}
+
+#[test]
+fn bits_for_share_count() {
+    assert_eq!(crate::max_distributable_shares(8).unwrap(), 255);
+    assert_eq!(crate::max_distributable_shares(20).unwrap(), 1048575);
+    assert_eq!(
+        crate::max_distributable_shares(21),
+        Err(BananaError::BitsOutOfRange(21))
+    );
+    assert_eq!(crate::recommend_bits(0), 3);
+    assert_eq!(crate::recommend_bits(7), 3);
+    assert_eq!(crate::recommend_bits(8), 4);
+    assert_eq!(crate::recommend_bits(255), 8);
+    assert_eq!(crate::recommend_bits(256), 9);
+    assert_eq!(crate::recommend_bits(usize::MAX), 20);
+}
+
+#[test]
+fn canonical_payload_ignores_cosmetics() {
+    let share = Share::new(hex::decode(SCAN_A1).unwrap()).unwrap();
+    let canonical = share.canonical_payload();
+    assert!(canonical.starts_with(b"{\"d\":\"8"));
+
+    // same share json, with shuffled keys and extra whitespace
+    let json: serde_json::Value = serde_json::from_slice(&hex::decode(SCAN_A1).unwrap()).unwrap();
+    let shuffled = format!(
+        "{{ \"n\" : {},\n \"d\": {}, \"r\": {}, \"t\": {}, \"v\": {} }}",
+        json["n"], json["d"], json["r"], json["t"], json["v"]
+    );
+    let share_shuffled = Share::new(shuffled.into_bytes()).unwrap();
+    assert_eq!(share_shuffled.canonical_payload(), canonical);
+
+    // canonical payload is a valid payload
+    let share_canonical = Share::new(canonical.to_vec()).unwrap();
+    assert!(share_canonical.diff(&share).is_identical());
+    assert_ne!(
+        Share::new(hex::decode(SCAN_A2).unwrap())
+            .unwrap()
+            .canonical_payload(),
+        canonical
+    );
+}
+
+#[test]
+fn trimmed_passphrase_retried() {
+    let combined = combined_from_secret(TITLE_CRAFTED, b"secret", "passphrase");
+    assert!(matches!(
+        combined.recover_with_passphrase("passphrase\n"),
+        Err(BananaError::DecodingFailed)
+    ));
+    assert_eq!(
+        combined
+            .recover_with_passphrase_trimmed("passphrase\n")
+            .unwrap(),
+        "secret"
+    );
+    assert!(matches!(
+        combined.recover_with_passphrase_trimmed("wrong passphrase\n"),
+        Err(BananaError::DecodingFailed)
+    ));
+}
+
+#[test]
+fn collection_sufficiency() {
+    let mut share_collection = ShareCollection::new();
+    assert_eq!(share_collection.sufficiency(), Sufficiency::NoShares);
+    let _ = share_collection
+        .add_share(Share::new(hex::decode(SCAN_A1).unwrap()).unwrap())
+        .unwrap();
+    assert_eq!(
+        share_collection.sufficiency(),
+        Sufficiency::Insufficient { have: 1, need: 2 }
+    );
+    let _ = share_collection
+        .add_share(Share::new(hex::decode(SCAN_A3).unwrap()).unwrap())
+        .unwrap();
+    assert_eq!(share_collection.sufficiency(), Sufficiency::Exact);
+    for _ in 0..2 {
+        assert_eq!(
+            share_collection
+                .add_share(Share::new(hex::decode(SCAN_A2).unwrap()).unwrap())
+                .unwrap(),
+            AddOutcome::AlreadyReady
+        );
+    }
+    assert_eq!(
+        share_collection.sufficiency(),
+        Sufficiency::Redundant { extra: 1 }
+    );
+}
+
+#[test]
+fn secret_checked_against_hash() {
+    let combined = combined_from_secret(TITLE_CRAFTED, b"secret", "passphrase");
+    let mut expected: [u8; 32] =
+        hex::decode("2bb80d537b1da3e38bd30361aa855686bde0eacd7162fef6a25fe97bf527a25b")
+            .unwrap()
+            .try_into()
+            .unwrap();
+    assert_eq!(
+        combined
+            .recover_and_check_hash("passphrase", &expected)
+            .unwrap(),
+        "secret"
+    );
+    expected[31] ^= 1;
+    assert!(matches!(
+        combined.recover_and_check_hash("passphrase", &expected),
+        Err(BananaError::SecretHashMismatch)
+    ));
+}
+
+#[test]
+fn lagrange_without_tables_matches() {
+    for bits in BIT_RANGE {
+        let (logs, exps) = generate_logs_and_exps(bits);
+        let polynomial = primitive_polynomial(bits);
+        let max = 2u32.pow(bits) - 1;
+        let x: Vec<u32> = (0..5).map(|i| (i * 977 + 1) % max + 1).collect();
+        for seed in 0..20u32 {
+            let y: Vec<u32> = (0..5)
+                .map(|i| seed.wrapping_mul(2654435761).wrapping_add(i * 40503) % (max + 1))
+                .collect();
+            assert_eq!(
+                lagrange(&x, &y, &logs, &exps, bits).unwrap(),
+                lagrange_without_tables(&x, &y, bits, polynomial).unwrap(),
+                "bits {bits}, y {y:?}"
+            );
+        }
+    }
+    assert!(matches!(
+        lagrange_without_tables(&[1, 1], &[1, 1], 8, primitive_polynomial(8)),
+        Err(BananaError::FieldInvariantViolated { .. })
+    ));
+}
+
+#[test]
+fn combined_within_memory_budget() {
+    let mut share_collection = ShareCollection::new();
+    for scan in [SCAN_C1, SCAN_C2, SCAN_C3] {
+        let _ = share_collection
+            .add_share_with_memory_budget(Share::new(hex::decode(scan).unwrap()).unwrap(), 0)
+            .unwrap();
+    }
+    if let ShareCollection::Ready(combined) = share_collection {
+        assert_eq!(
+            combined.recover_with_passphrase(PASSPHRASE_C).unwrap(),
+            ALICE_SEEDPHRASE
+        );
+    } else {
+        panic!("Added 3 different shares out of required 3. Must be `Ready` variant.")
+    }
+
+    // tables for bits `9` take 4 KiB, recovery is same without and with them
+    for budget in [0, 4096] {
+        let mut share_collection = ShareCollection::new();
+        for share in bits9_shares() {
+            let _ = share_collection
+                .add_share_with_memory_budget(share, budget)
+                .unwrap();
+        }
+        assert_eq!(
+            share_collection
+                .take_ready()
+                .unwrap()
+                .recover_with_passphrase(PASSPHRASE_A)
+                .unwrap(),
+            ALICE_SEEDPHRASE
+        );
+    }
+}
+
+#[test]
+fn secret_kind_from_title() {
+    let titled = |title: &str| {
+        let json = format!(
+            "{{\"v\":1,\"t\":{},\"r\":2,\"d\":\"8AQ==\",\"n\":\"{}\"}}",
+            serde_json::to_string(title).unwrap(),
+            NONCE_CRAFTED
+        );
+        Share::new(json.into_bytes())
+            .unwrap()
+            .inferred_secret_kind()
+    };
+    assert_eq!(titled("BTC seed"), Some(SecretKind::Mnemonic));
+    assert_eq!(titled("my-Mnemonic"), Some(SecretKind::Mnemonic));
+    assert_eq!(titled("SSH key"), Some(SecretKind::Text));
+    assert_eq!(titled("hex key"), Some(SecretKind::Hex));
+    assert_eq!(titled("seedless"), None);
+    assert_eq!(titled("seed and password"), None);
+    assert_eq!(
+        Share::new(hex::decode(SCAN_A1).unwrap())
+            .unwrap()
+            .inferred_secret_kind(),
+        None
+    );
+}
+
+#[test]
+fn nonce_compared_as_bytes() {
+    let mut in_progress = SetInProgress::init(share_with_content(1, b"content", 3));
+
+    // same nonce, with json-escaped `+`
+    let json = crafted_json(
+        3,
+        &format!("8{}", base64::encode(b"\x02Content")),
+        &NONCE_CRAFTED.replace('+', "\\u002B"),
+    );
+    in_progress
+        .add_share(Share::new(json.into_bytes()).unwrap())
+        .unwrap();
+
+    // nonce with a single byte changed
+    let mut nonce = base64::decode(NONCE_CRAFTED).unwrap();
+    nonce[23] ^= 1;
+    let json = crafted_json(
+        3,
+        &format!("8{}", base64::encode(b"\x03content")),
+        &base64::encode(nonce),
+    );
+    assert!(matches!(
+        in_progress.add_share(Share::new(json.into_bytes()).unwrap()),
+        Err(BananaError::ShareNonceDifferent)
+    ));
+    assert_eq!(in_progress.shares_now(), 2);
+}
+
+#[test]
+fn collections_combined() {
+    let collection = |scans: &[&str]| {
+        let mut share_collection = ShareCollection::new();
+        for scan in scans {
+            let _ = share_collection
+                .add_share(Share::new(hex::decode(scan).unwrap()).unwrap())
+                .unwrap();
+        }
+        share_collection
+    };
+
+    let combined = combine_collections(vec![
+        collection(&[SCAN_C1, SCAN_C2]),
+        ShareCollection::new(),
+        collection(&[SCAN_C2, SCAN_C3]),
+    ])
+    .unwrap();
+    assert_eq!(
+        combined.recover_with_passphrase(PASSPHRASE_C).unwrap(),
+        ALICE_SEEDPHRASE
+    );
+
+    assert!(matches!(
+        combine_collections(vec![collection(&[SCAN_C1]), collection(&[SCAN_C1])]),
+        Err(BananaError::ThresholdUnreachable { have: 1, need: 3 })
+    ));
+    assert_eq!(
+        combine_collections(vec![collection(&[SCAN_C1]), collection(&[SCAN_A1])]).unwrap_err(),
+        BananaError::IncompatibleCollections {
+            reason: Box::new(BananaError::ShareAlreadyInSet)
+        }
+    );
+    match combine_collections(vec![collection(&[SCAN_C1]), collection(&[SCAN_A2])]) {
+        Err(BananaError::IncompatibleCollections { reason }) => {
+            assert!(matches!(*reason, BananaError::ShareTitleDifferent { .. }))
+        }
+        other => panic!("Expected incompatible collections, got {:?}", other),
+    }
+    assert!(matches!(
+        combine_collections(Vec::new()),
+        Err(BananaError::NoShares)
+    ));
+}
+
+/// Deterministic random number generator, for tests only.
+struct TestRng(u64);
+
+impl rand_core::RngCore for TestRng {
+    fn next_u32(&mut self) -> u32 {
+        self.next_u64() as u32
+    }
+    fn next_u64(&mut self) -> u64 {
+        // xorshift64
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        rand_core::impls::fill_bytes_via_next(self, dest)
+    }
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl rand_core::CryptoRng for TestRng {}
+
+#[test]
+fn split_and_recovered() {
+    let shares = split_secret(
+        "alice splits",
+        ALICE_SEEDPHRASE,
+        5,
+        3,
+        PASSPHRASE_C,
+        &mut TestRng(0x5eed),
+    )
+    .unwrap();
+    assert_eq!(shares.len(), 5);
+
+    for subset in [[0, 1, 2], [1, 3, 4]] {
+        let mut share_collection = ShareCollection::new();
+        for i in subset {
+            // through share json, as if scanned
+            let _ = share_collection
+                .add_share(Share::new(shares[i].to_qr_payload()).unwrap())
+                .unwrap();
+        }
+        if let ShareCollection::Ready(ref combined) = share_collection {
+            assert_eq!(
+                combined.recover_with_passphrase(PASSPHRASE_C).unwrap(),
+                ALICE_SEEDPHRASE
+            );
+        } else {
+            panic!("Added 3 different shares out of required 3. Must be `Ready` variant.")
+        }
+    }
+
+    assert!(matches!(
+        split_secret("title", "secret", 2, 3, "passphrase", &mut TestRng(1)),
+        Err(BananaError::SplitParametersInvalid {
+            total_shares: 2,
+            required_shares: 3
+        })
+    ));
+    assert!(matches!(
+        split_secret("title", "secret", 256, 3, "passphrase", &mut TestRng(1)),
+        Err(BananaError::SplitParametersInvalid { .. })
+    ));
+}
+
+#[test]
+fn share_accessors() {
+    for (id, scan) in [(1, SCAN_A1), (2, SCAN_A2), (3, SCAN_A3)] {
+        let share = Share::new(hex::decode(scan).unwrap()).unwrap();
+        assert_eq!(share.id(), id);
+        assert_eq!(share.required_shares(), 2);
+        assert_eq!(share.bits(), 8);
+        assert_eq!(share.version(), Version::V1);
+        assert_eq!(
+            share.nonce(),
+            base64::decode("o9DbpBi9r7UWJHOriuDArR4Vrc0VOo3l").unwrap()
+        );
+        assert_eq!(share.content_len(), 96);
+    }
+}
+
+#[test]
+fn extra_share_checked_when_ready() {
+    let scanned = |scan: &str| Share::new(hex::decode(scan).unwrap()).unwrap();
+    let mut share_collection = ShareCollection::new();
+    assert_eq!(
+        share_collection.add_share(scanned(SCAN_C1)).unwrap(),
+        AddOutcome::InProgress
+    );
+    assert_eq!(
+        share_collection.add_share(scanned(SCAN_C2)).unwrap(),
+        AddOutcome::InProgress
+    );
+    assert_eq!(
+        share_collection.add_share(scanned(SCAN_C3)).unwrap(),
+        AddOutcome::Ready
+    );
+
+    let share4 = if let ShareCollection::Ready(ref combined) = share_collection {
+        combined
+            .regenerate_share(PASSPHRASE_C, 4, &[1, 2, 3])
+            .unwrap()
+    } else {
+        panic!("Added 3 different shares out of required 3. Must be `Ready` variant.")
+    };
+    assert_eq!(
+        share_collection.add_share(share4).unwrap(),
+        AddOutcome::AlreadyReady
+    );
+    assert!(matches!(
+        share_collection.add_share(scanned(SCAN_C1)),
+        Err(BananaError::ShareAlreadyInSet)
+    ));
+    assert!(matches!(
+        share_collection.add_share(scanned(SCAN_A1)),
+        Err(BananaError::ShareTitleDifferent { .. })
+    ));
+    assert_eq!(
+        share_collection.sufficiency(),
+        Sufficiency::Redundant { extra: 1 }
+    );
+}
+
+#[test]
+fn key_derived_once() {
+    let combined = combined_from_secret(TITLE_CRAFTED, b"secret", "passphrase");
+    let key = combined.derive_key("passphrase").unwrap();
+    assert_eq!(combined.decrypt_with_key(&key).unwrap(), "secret");
+    assert_eq!(combined.decrypt_with_key(&key).unwrap(), "secret");
+    assert_eq!(format!("{key:?}"), "RecoveryKey(..)");
+
+    let wrong_key = combined.derive_key("passphrase\n").unwrap();
+    assert!(matches!(
+        combined.decrypt_with_key(&wrong_key),
+        Err(BananaError::DecodingFailed)
+    ));
+}
+
+#[test]
+fn binary_secret_recovered() {
+    let secret = [0xff, 0x00, 0xfe, 0x80];
+    let combined = combined_from_secret(TITLE_CRAFTED, &secret, "passphrase");
+    assert_eq!(
+        *combined
+            .recover_bytes_with_passphrase("passphrase")
+            .unwrap(),
+        secret
+    );
+    assert!(matches!(
+        combined.recover_with_passphrase("passphrase"),
+        Err(BananaError::DecodedSecretNotString)
+    ));
+    assert!(matches!(
+        combined.recover_bytes_with_passphrase("wrong passphrase"),
+        Err(BananaError::DecodingFailed)
+    ));
+}
+
+#[test]
+fn nonce_length_checked() {
+    let with_nonce = |nonce: &str| {
+        let json = crafted_json(2, &format!("8{}", base64::encode(b"\x01content")), nonce);
+        Share::new(json.into_bytes())
+    };
+    assert!(with_nonce(NONCE_CRAFTED).is_ok());
+
+    // truncated nonce
+    assert!(matches!(
+        with_nonce(&NONCE_CRAFTED[..28]),
+        Err(BananaError::NonceWrongLength(21))
+    ));
+    assert!(matches!(
+        with_nonce(&NONCE_CRAFTED[..30]),
+        Err(BananaError::NonceNotBase64)
+    ));
+
+    // 16-byte nonce
+    assert!(matches!(
+        with_nonce(&base64::encode([7u8; 16])),
+        Err(BananaError::NonceWrongLength(16))
+    ));
+}
+
+#[test]
+fn zero_share_id_rejected() {
+    let json = crafted_json(
+        2,
+        &format!("8{}", base64::encode(b"\x00content")),
+        NONCE_CRAFTED,
+    );
+    assert!(matches!(
+        Share::new(json.into_bytes()),
+        Err(BananaError::ShareIdInvalid(0))
+    ));
+}
+
+#[test]
+fn progress_summary() {
+    let mut in_progress = SetInProgress::init(share_with_content(1, b"content", 3));
+    in_progress
+        .add_share(share_with_content(2, b"Content", 3))
+        .unwrap();
+    assert_eq!(in_progress.shares_missing(), 1);
+    assert_eq!(
+        in_progress.progress(),
+        Progress {
+            collected: 2,
+            required: 3,
+            missing: 1,
+            title: String::from(TITLE_CRAFTED),
+            bits: 8,
+        }
+    );
+
+    // more shares than required
+    let mut in_progress = SetInProgress::init(share_with_content(1, b"content", 2));
+    in_progress
+        .add_share(share_with_content(2, b"Content", 2))
+        .unwrap();
+    in_progress
+        .add_share(share_with_content(3, b"CONTENT", 2))
+        .unwrap();
+    assert_eq!(in_progress.shares_missing(), 0);
+    assert_eq!(in_progress.progress().missing, 0);
+}
+
+#[test]
+fn share_removed_and_added_again() {
+    let scanned = |scan: &str| Share::new(hex::decode(scan).unwrap()).unwrap();
+    let mut share_collection = ShareCollection::new();
+    let _ = share_collection.add_share(scanned(SCAN_C1)).unwrap();
+    let _ = share_collection.add_share(scanned(SCAN_C2)).unwrap();
+
+    share_collection.remove_share(1).unwrap();
+    assert_eq!(
+        share_collection.sufficiency(),
+        Sufficiency::Insufficient { have: 1, need: 3 }
+    );
+    assert!(matches!(
+        share_collection.remove_share(1),
+        Err(BananaError::ShareIdNotInSet(1))
+    ));
+
+    // same share is accepted again
+    let _ = share_collection.add_share(scanned(SCAN_C1)).unwrap();
+    assert_eq!(
+        share_collection.add_share(scanned(SCAN_C3)).unwrap(),
+        AddOutcome::Ready
+    );
+
+    // back to in progress, and then to empty
+    share_collection.remove_share(3).unwrap();
+    assert_eq!(
+        share_collection.sufficiency(),
+        Sufficiency::Insufficient { have: 2, need: 3 }
+    );
+    share_collection.remove_share(2).unwrap();
+    share_collection.remove_share(1).unwrap();
+    assert_eq!(share_collection.sufficiency(), Sufficiency::NoShares);
+}
+
+#[test]
+fn collected_ids() {
+    let mut share_collection = ShareCollection::new();
+    assert!(share_collection.ids().is_empty());
+    for scan in [SCAN_C3, SCAN_C1] {
+        let _ = share_collection
+            .add_share(Share::new(hex::decode(scan).unwrap()).unwrap())
+            .unwrap();
+    }
+    if let ShareCollection::InProgress(ref in_progress) = share_collection {
+        assert_eq!(in_progress.ids(), &[3, 1]);
+        assert!(in_progress.contains_id(3));
+        assert!(!in_progress.contains_id(2));
+    } else {
+        panic!("Added 2 different shares out of required 3. Must be `InProgress` variant.")
+    }
+    let _ = share_collection
+        .add_share(Share::new(hex::decode(SCAN_C2).unwrap()).unwrap())
+        .unwrap();
+    assert_eq!(share_collection.ids(), &[3, 1, 2]);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn collection_restored() {
+    let mut share_collection = ShareCollection::new();
+    for scan in [SCAN_C1, SCAN_C2] {
+        let _ = share_collection
+            .add_share(Share::new(hex::decode(scan).unwrap()).unwrap())
+            .unwrap();
+    }
+    let stored = serde_json::to_string(&share_collection).unwrap();
+
+    let mut restored: ShareCollection = serde_json::from_str(&stored).unwrap();
+    assert_eq!(restored.ids(), &[1, 2]);
+    assert_eq!(
+        restored
+            .add_share(Share::new(hex::decode(SCAN_C3).unwrap()).unwrap())
+            .unwrap(),
+        AddOutcome::Ready
+    );
+
+    // ready collection is combined again on restoring
+    let stored = serde_json::to_string(&restored).unwrap();
+    let restored: SetCombined = serde_json::from_str(&stored).unwrap();
+    assert_eq!(
+        restored.recover_with_passphrase(PASSPHRASE_C).unwrap(),
+        ALICE_SEEDPHRASE
+    );
+
+    // stored data is checked
+    let mut shares: Vec<String> = serde_json::from_str(&stored).unwrap();
+    shares[1] = shares[0].clone();
+    let tampered = serde_json::to_string(&shares).unwrap();
+    assert!(serde_json::from_str::<ShareCollection>(&tampered).is_err());
+    assert!(serde_json::from_str::<SetInProgress>("[]").is_err());
+    assert!(serde_json::from_str::<Share>("\"share\"").is_err());
+}
+
+#[test]
+fn compatibility_checked_without_adding() {
+    let in_progress = SetInProgress::init(share_with_content(1, b"content", 2));
+    let share = |v: &str, t: &str, r: usize, d: &str, n: &str| {
+        let json = format!(r#"{{{}"t":"{}","r":{},"d":"{}","n":"{}"}}"#, v, t, r, d, n);
+        Share::new(json.into_bytes()).unwrap()
+    };
+    let v1 = r#""v":1,"#;
+    let body = format!("8{}", base64::encode(b"\x02Content"));
+    let other_nonce = base64::encode([7u8; 24]);
+
+    assert!(in_progress
+        .would_accept(&share(v1, TITLE_CRAFTED, 2, &body, NONCE_CRAFTED))
+        .is_ok());
+    assert!(matches!(
+        in_progress.would_accept(&share(
+            "",
+            TITLE_CRAFTED,
+            2,
+            &format!("8{}", hex::encode(b"\x02content")),
+            NONCE_CRAFTED
+        )),
+        Err(BananaError::ShareVersionDifferent)
+    ));
+    assert_eq!(
+        in_progress.would_accept(&share(v1, "other", 2, &body, NONCE_CRAFTED)),
+        Err(BananaError::ShareTitleDifferent {
+            set: String::from(TITLE_CRAFTED),
+            new_share: String::from("other"),
+        })
+    );
+    assert!(matches!(
+        in_progress.would_accept(&share(v1, TITLE_CRAFTED, 3, &body, NONCE_CRAFTED)),
+        Err(BananaError::ShareRequiredSharesDifferent)
+    ));
+    assert!(matches!(
+        in_progress.would_accept(&share(v1, TITLE_CRAFTED, 2, &body, &other_nonce)),
+        Err(BananaError::ShareNonceDifferent)
+    ));
+    assert!(matches!(
+        in_progress.would_accept(&share(
+            v1,
+            TITLE_CRAFTED,
+            2,
+            &format!("9{}", base64::encode(b"\x00\x02content")),
+            NONCE_CRAFTED
+        )),
+        Err(BananaError::ShareBitsDifferent)
+    ));
+    assert!(matches!(
+        in_progress.would_accept(&share_with_content(1, b"content", 2)),
+        Err(BananaError::ShareAlreadyInSet)
+    ));
+    assert!(matches!(
+        in_progress.would_accept(&share_with_content(2, b"longer content", 2)),
+        Err(BananaError::ShareContentLengthDifferent)
+    ));
+    assert!(matches!(
+        in_progress.would_accept(&share_with_content(2, b"content", 2)),
+        Err(BananaError::ShareContentDuplicate {
+            existing_id: 1,
+            new_id: 2
+        })
+    ));
+    assert!(in_progress
+        .would_accept_lenient(&share_with_content(2, b"content", 2))
+        .is_ok());
+    assert_eq!(in_progress.shares_now(), 1);
+}
+
+#[test]
+fn interleaved_sets_sorted() {
+    let mut multi_collection = MultiShareCollection::new();
+    for scan in [SCAN_C1, SCAN_A1, SCAN_C2, SCAN_A3, SCAN_C3] {
+        let _ = multi_collection
+            .add_share(Share::new(hex::decode(scan).unwrap()).unwrap())
+            .unwrap();
+    }
+    assert_eq!(multi_collection.sets().len(), 2);
+    assert_eq!(multi_collection.sets()[0].ids(), &[1, 2, 3]);
+    assert_eq!(multi_collection.sets()[1].ids(), &[1, 3]);
+
+    let combined: Vec<&SetCombined> = multi_collection.ready().collect();
+    assert_eq!(combined.len(), 2);
+    assert_eq!(
+        combined[0].recover_with_passphrase(PASSPHRASE_C).unwrap(),
+        ALICE_SEEDPHRASE
+    );
+    assert_eq!(
+        combined[1].recover_with_passphrase(PASSPHRASE_A).unwrap(),
+        ALICE_SEEDPHRASE
+    );
+}
+
+#[test]
+fn debug_output_redacted() {
+    let content = b"very secret share content";
+    let share = share_with_content(1, content, 2);
+    let nonce_hex = hex::encode(base64::decode(NONCE_CRAFTED).unwrap());
+    let debug = format!("{share:?}");
+    assert!(debug.contains(TITLE_CRAFTED));
+    assert!(debug.contains("content: [25 bytes]"));
+    for string in [hex::encode(content), format!("{:?}", &content[..])] {
+        assert!(!debug.contains(&string));
+    }
+
+    let in_progress = SetInProgress::init(share_with_content(1, content, 2));
+    let debug = format!("{in_progress:?}");
+    assert!(debug.contains("id_set: [1]"));
+    assert!(!debug.contains(&format!("{:?}", &content[..])));
+
+    let combined = combined_from_secret(TITLE_CRAFTED, content, "passphrase");
+    let debug = format!("{combined:?}");
+    assert!(debug.contains("nonce: [24 bytes]"));
+    for secret_hex in [hex::encode(content), nonce_hex] {
+        assert!(!debug.contains(&secret_hex));
+    }
+}
+
+#[test]
+fn secret_in_zeroizing_wrapper() {
+    let mut share_collection = ShareCollection::new();
+    for scan in [SCAN_A1, SCAN_A2] {
+        let _ = share_collection
+            .add_share(Share::new(hex::decode(scan).unwrap()).unwrap())
+            .unwrap();
+    }
+    if let ShareCollection::Ready(combined) = share_collection {
+        let secret = combined
+            .recover_with_passphrase_secure(PASSPHRASE_A)
+            .unwrap();
+        assert_eq!(secret.as_str(), ALICE_SEEDPHRASE);
+    } else {
+        panic!("Added 2 different shares out of required 2. Must be `Ready` variant.")
+    }
+}
+
+#[test]
+fn url_safe_unpadded_base64_accepted() {
+    let reencoded = |scan: &str| {
+        let mut json: serde_json::Value =
+            serde_json::from_slice(&hex::decode(scan).unwrap()).unwrap();
+        for key in ["d", "n"] {
+            let value = json[key]
+                .as_str()
+                .unwrap()
+                .replace('+', "-")
+                .replace('/', "_")
+                .replace('=', "");
+            json[key] = serde_json::Value::String(value);
+        }
+        serde_json::to_vec(&json).unwrap()
+    };
+    let payloads = vec![reencoded(SCAN_A1), reencoded(SCAN_A2)];
+    assert!(payloads
+        .iter()
+        .all(|a| a.contains(&b'-') || a.contains(&b'_')));
+    assert!(!payloads.iter().any(|a| a.contains(&b'=')));
+    assert_eq!(
+        crate::recover(&payloads, PASSPHRASE_A).unwrap(),
+        ALICE_SEEDPHRASE
+    );
+}
+
+#[test]
+fn payload_noise_trimmed() {
+    let clean = Share::new(hex::decode(SCAN_A1).unwrap()).unwrap();
+    let payload = hex::decode(SCAN_A1).unwrap();
+    for noisy in [
+        [&payload[..], b"\n"].concat(),
+        [&payload[..], b"\r\n"].concat(),
+        [&payload[..], b" "].concat(),
+        [&payload[..], b"\0\0"].concat(),
+        ["\u{feff}".as_bytes(), &payload[..]].concat(),
+    ] {
+        assert!(detect(&noisy).is_some());
+        assert!(Share::new(noisy).unwrap().diff(&clean).is_identical());
+    }
+}
+
+#[test]
+fn parsing_errors_detailed() {
+    let payload = hex::decode(SCAN_A1).unwrap();
+    match Share::new(payload[..40].to_vec()) {
+        Err(BananaError::JsonParsing(reason)) => {
+            assert!(reason.contains("EOF"));
+            assert!(reason.contains("line 1 column 40"));
+        }
+        _ => panic!("Truncated json must fail to parse."),
+    }
+    assert!(matches!(
+        Share::new([&payload[..5], b"\xff", &payload[5..]].concat()),
+        Err(BananaError::NotShareString(5))
+    ));
+}
+
+#[test]
+fn share_from_str() {
+    let clean = Share::new(hex::decode(SCAN_A1).unwrap()).unwrap();
+    let json = String::from_utf8(hex::decode(SCAN_A1).unwrap()).unwrap();
+    for share in [
+        Share::from_hex_payload(SCAN_A1).unwrap(),
+        Share::from_json_str(&json).unwrap(),
+        SCAN_A1.parse::<Share>().unwrap(),
+        json.parse::<Share>().unwrap(),
+    ] {
+        assert!(share.diff(&clean).is_identical());
+    }
+    assert!(matches!(
+        Share::from_hex_payload("7b22zz"),
+        Err(BananaError::PayloadNotHex)
+    ));
+    assert!(matches!(
+        "{\"v\":1".parse::<Share>(),
+        Err(BananaError::JsonParsing(_))
+    ));
+}
+
+#[test]
+fn collection_from_shares() {
+    let shares = [SCAN_C1, SCAN_C2, SCAN_C3].map(|scan| Share::from_hex_payload(scan).unwrap());
+    if let ShareCollection::Ready(combined) = ShareCollection::from_shares(shares).unwrap() {
+        let alice_secret = combined.recover_with_passphrase(PASSPHRASE_C).unwrap();
+        assert_eq!(alice_secret, ALICE_SEEDPHRASE);
+    } else {
+        panic!("Three different shares are sufficient.")
+    }
+
+    let shares = [SCAN_C1, SCAN_B1, SCAN_C2].map(|scan| Share::from_hex_payload(scan).unwrap());
+    assert_eq!(
+        ShareCollection::from_shares(shares).unwrap_err(),
+        BananaError::ShareRejected {
+            index: 1,
+            reason: Box::new(BananaError::ShareTitleDifferent {
+                set: String::from("alice has too many friends"),
+                new_share: String::from("terrible\"truth\\\"escaping"),
+            }),
+        }
+    );
+
+    let mut share_collection = ShareCollection::new();
+    share_collection
+        .try_extend([Share::from_hex_payload(SCAN_C1).unwrap()])
+        .unwrap();
+    assert!(matches!(share_collection, ShareCollection::InProgress(_)));
+}
+
+#[test]
+fn collection_accessors() {
+    let mut share_collection = ShareCollection::new();
+    assert_eq!(share_collection.title(), None);
+    assert_eq!(share_collection.shares_now(), 0);
+    assert_eq!(share_collection.shares_required(), None);
+    assert!(share_collection.in_progress().is_none());
+    assert!(share_collection.ready().is_none());
+
+    for scan in [SCAN_C1, SCAN_C2, SCAN_C3] {
+        let _ = share_collection
+            .add_share(Share::from_hex_payload(scan).unwrap())
+            .unwrap();
+    }
+    assert_eq!(share_collection.title(), Some("alice has too many friends"));
+    assert_eq!(share_collection.shares_now(), 3);
+    assert_eq!(share_collection.shares_required(), Some(3));
+    assert!(share_collection.ready().is_some());
+    assert!(share_collection.take_ready().is_ok());
+    assert!(ShareCollection::new().take_ready().is_err());
+}
+
+#[test]
+fn builtin_tables_match_generated() {
+    let (logs, exps) = generate_logs_and_exps(8);
+    let builtin = FieldTables::new(8);
+    assert_eq!(builtin.logs(), &logs[..]);
+    assert_eq!(builtin.exps(), &exps[..]);
+}
+
+#[test]
+fn tables_retained_in_set() {
+    // linear polynomial `0x11 + 0x22 x` in `GF(2^9)`
+    let mut set = SetInProgress::init(share_with_bits9_content(1, &[0x33], 2));
+    for (id, content) in [(2, 0x55), (3, 0x77)] {
+        set.add_share(share_with_bits9_content(id, &[content], 2))
+            .unwrap();
+    }
+    let first = set.combine_raw_bits().unwrap();
+    let logs_ptr = set.tables().logs().as_ptr();
+    let exps_ptr = set.tables().exps().as_ptr();
+
+    // combining twice, cross-checking and deriving do not rebuild the tables
+    assert_eq!(set.combine_raw_bits().unwrap(), first);
+    let _ = set.consistency_check().unwrap();
+    let _ = set.derive_new_share(4).unwrap();
+    assert_eq!(set.tables().logs().as_ptr(), logs_ptr);
+    assert_eq!(set.tables().exps().as_ptr(), exps_ptr);
+}
+
+#[test]
+fn large_secret_recovered() {
+    let secret: String = (0..4096u32)
+        .map(|i| char::from(b'a' + (i * 7 % 26) as u8))
+        .collect();
+    let shares = split_secret(
+        "large secret",
+        &secret,
+        4,
+        3,
+        PASSPHRASE_C,
+        &mut TestRng(0xb16),
+    )
+    .unwrap();
+    let share_collection = ShareCollection::from_shares(shares.into_iter().skip(1)).unwrap();
+    let combined = share_collection.take_ready().unwrap();
+    assert_eq!(
+        combined.recover_with_passphrase(PASSPHRASE_C).unwrap(),
+        secret
+    );
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn parallel_combining_matches_sequential() {
+    let mut rng = TestRng(0x16b);
+    let mut share_collection = ShareCollection::new();
+    for id in 1..=3 {
+        let mut content = vec![0u8; 16 * 1024];
+        rand_core::RngCore::fill_bytes(&mut rng, &mut content);
+        let _ = share_collection
+            .add_share(share_with_content(id, &content, 4))
+            .unwrap();
+    }
+    let in_progress = share_collection.in_progress().unwrap();
+    let (logs, exps) = generate_logs_and_exps(8);
+    let interpolate = |y: &[u32]| lagrange(in_progress.ids(), y, &logs, &exps, 8);
+    assert_eq!(
+        in_progress.raw_bits_parallel(interpolate).unwrap(),
+        in_progress.raw_bits_sequential(interpolate).unwrap()
+    );
+}
+
+#[test]
+fn recovered_with_scrypt_params() {
+    let title = "forked";
+    let params = ScryptParams::new(14, 8, 1).unwrap();
+    let mut hasher = Sha512::new();
+    hasher.update(title.as_bytes());
+    let mut key = [0u8; 32];
+    scrypt(
+        PASSPHRASE_A.as_bytes(),
+        &hasher.finalize(),
+        &params,
+        &mut key,
+    )
+    .unwrap();
+    let nonce = [7u8; 24];
+    let data = encrypt_with_key(&key, &nonce, ALICE_SEEDPHRASE.as_bytes());
+    let combined = SetCombined::from_parts(title, data, nonce.to_vec()).unwrap();
+
+    assert_eq!(
+        combined
+            .recover_with_passphrase_params(PASSPHRASE_A, params)
+            .unwrap(),
+        ALICE_SEEDPHRASE
+    );
+    assert!(matches!(
+        combined.recover_with_passphrase(PASSPHRASE_A),
+        Err(BananaError::DecodingFailed)
+    ));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn recovered_with_progress() {
+    let share_collection = ShareCollection::from_shares(
+        [SCAN_A1, SCAN_A2].map(|scan| Share::from_hex_payload(scan).unwrap()),
+    )
+    .unwrap();
+    let combined = share_collection.take_ready().unwrap();
+    let mut reported = Vec::new();
+    assert_eq!(
+        combined
+            .recover_with_passphrase_with_progress(PASSPHRASE_A, |x| reported.push(x))
+            .unwrap(),
+        ALICE_SEEDPHRASE
+    );
+    assert_eq!(reported.first(), Some(&0.1));
+    assert_eq!(&reported[reported.len() - 2..], &[0.9, 1.0]);
+    assert!(reported.windows(2).all(|x| x[0] < x[1]));
+
+    reported.clear();
+    assert!(matches!(
+        combined.recover_with_passphrase_with_progress("wrong", |x| reported.push(x)),
+        Err(BananaError::DecodingFailed)
+    ));
+    assert_eq!(reported.last(), Some(&1.0));
+}
+
+#[test]
+fn recovered_with_passphrase_bytes() {
+    let combined = combined_from_secret("bytes", ALICE_SEEDPHRASE.as_bytes(), PASSPHRASE_A);
+    assert_eq!(
+        combined
+            .recover_with_passphrase_bytes(PASSPHRASE_A.as_bytes())
+            .unwrap(),
+        combined.recover_with_passphrase(PASSPHRASE_A).unwrap()
+    );
+    assert!(matches!(
+        combined.recover_with_passphrase_bytes(b"blighted-comprised-bucktooth-disjoin\xa0"),
+        Err(BananaError::DecodingFailed)
+    ));
+}
+
+#[test]
+fn recovered_with_passphrase_candidates() {
+    assert_eq!(
+        passphrase::normalize(" Blighted comprised  bucktooth\tdisjoin "),
+        PASSPHRASE_A
+    );
+
+    let combined = combined_from_secret("typos", ALICE_SEEDPHRASE.as_bytes(), PASSPHRASE_A);
+    assert_eq!(
+        combined
+            .recover_with_candidates(
+                "Blighted comprised  bucktooth disjoin ",
+                CandidateOptions::default()
+            )
+            .unwrap(),
+        (
+            String::from(ALICE_SEEDPHRASE),
+            PassphraseVariant::Normalized
+        )
+    );
+    assert!(matches!(
+        combined.recover_with_candidates(
+            "Blighted comprised  bucktooth disjoin ",
+            CandidateOptions { max_attempts: 1 }
+        ),
+        Err(BananaError::DecodingFailed)
+    ));
+}
+
+#[cfg(feature = "generator")]
+#[test]
+fn passphrase_generated() {
+    assert_eq!(
+        WORDLIST_LENGTH as usize,
+        include_str!("eff_large_wordlist.txt")
+            .lines()
+            .filter(|word| !word.contains('-'))
+            .count()
+    );
+    let generated = passphrase::generate(4, &mut TestRng(0x5eed));
+    assert_eq!(generated, passphrase::generate(4, &mut TestRng(0x5eed)));
+    assert_ne!(generated, passphrase::generate(4, &mut TestRng(0x5eee)));
+    assert_eq!(generated, passphrase::normalize(&generated));
+    assert_eq!(generated.split('-').count(), 4);
+    for seed in 1..=256 {
+        let generated = passphrase::generate(3, &mut TestRng(seed));
+        assert_eq!(generated.split('-').count(), 3);
+    }
+    assert_eq!(passphrase::generate(0, &mut TestRng(1)), "");
+}
+
+#[test]
+fn new_share_derived_without_passphrase() {
+    let share_collection = ShareCollection::from_shares(
+        [SCAN_A1, SCAN_A2].map(|scan| Share::from_hex_payload(scan).unwrap()),
+    )
+    .unwrap();
+    let combined = share_collection.take_ready().unwrap();
+
+    // ids already in set and out of range ids could not be derived
+    assert!(matches!(
+        combined.derive_new_share(2),
+        Err(BananaError::ShareAlreadyInSet)
+    ));
+    assert!(matches!(
+        combined.derive_new_share(256),
+        Err(BananaError::ShareIdInvalid(256))
+    ));
+
+    // derived share survives json round trip, and works with original share
+    let share5 = combined.derive_new_share(5).unwrap();
+    let share5 = Share::new(share5.to_qr_payload()).unwrap();
+    assert_eq!(share5.id(), 5);
+    assert_eq!(share5.title(), "Alice tries BananaSplit again");
+    let share_collection =
+        ShareCollection::from_shares([share5, Share::from_hex_payload(SCAN_A3).unwrap()]).unwrap();
+    let combined = share_collection.take_ready().unwrap();
+    assert_eq!(
+        combined.recover_with_passphrase(PASSPHRASE_A).unwrap(),
+        ALICE_SEEDPHRASE
+    );
+
+    let mut share_collection = ShareCollection::new();
+    let _ = share_collection
+        .add_share(Share::from_hex_payload(SCAN_A1).unwrap())
+        .unwrap();
+    assert!(matches!(
+        share_collection.in_progress().unwrap().derive_new_share(5),
+        Err(BananaError::ThresholdUnreachable { have: 1, need: 2 })
+    ));
+}
+
+/// Crafted share with bits `9`, share id takes 2 bytes.
+fn share_with_bits9_content(id: u16, content: &[u8], required_shares: usize) -> Share {
+    let body = [id.to_be_bytes().to_vec(), content.to_vec()].concat();
+    let json = crafted_json(
+        required_shares,
+        &format!("9{}", base64::encode(body)),
+        NONCE_CRAFTED,
+    );
+    Share::new(json.into_bytes()).unwrap()
+}
+
+#[test]
+fn new_share_derived_for_bits_above_8() {
+    // linear polynomial `0x11 + 0x22 x` in `GF(2^9)`, all values fit in bytes
+    let mut set = SetInProgress::init(share_with_bits9_content(1, &[0x33], 2));
+    set.add_share(share_with_bits9_content(2, &[0x55], 2))
+        .unwrap();
+    let raw_bits = set.combine_raw_bits().unwrap();
+
+    let share3 = set.derive_new_share(3).unwrap();
+    let share3 = Share::new(share3.to_qr_payload()).unwrap();
+    let mut derived_set = SetInProgress::init(share_with_bits9_content(1, &[0x33], 2));
+    derived_set.add_share(share3).unwrap();
+    assert_eq!(derived_set.combine_raw_bits().unwrap(), raw_bits);
+
+    // polynomial `0x100 + 0x180 x`, value at `3` is `0x191`
+    let mut set = SetInProgress::init(share_with_bits9_content(1, &[0x80], 2));
+    set.add_share(share_with_bits9_content(2, &[0x11], 2))
+        .unwrap();
+    assert!(matches!(
+        set.derive_new_share(3),
+        Err(BananaError::DerivedShareUnrepresentable(3))
+    ));
+}
+
+#[test]
+fn combined_from_parts() {
+    let share_collection = ShareCollection::from_shares(
+        [SCAN_A1, SCAN_A3].map(|scan| Share::from_hex_payload(scan).unwrap()),
+    )
+    .unwrap();
+    let combined = share_collection.take_ready().unwrap();
+    assert_eq!(
+        combined.salt(),
+        <[u8; 64]>::from(Sha512::digest(combined.title().as_bytes()))
+    );
+
+    let restored = SetCombined::from_parts(
+        combined.title(),
+        combined.encrypted_data().to_vec(),
+        combined.nonce().to_vec(),
+    )
+    .unwrap();
+    assert_eq!(
+        restored.recover_with_passphrase(PASSPHRASE_A).unwrap(),
+        ALICE_SEEDPHRASE
+    );
+    assert!(matches!(
+        restored.derive_new_share(4),
+        Err(BananaError::NoShares)
+    ));
+    assert!(matches!(
+        restored.regenerate_share(PASSPHRASE_A, 4, &[]),
+        Err(BananaError::ThresholdUnreachable { have: 0, need: 1 })
+    ));
+    assert!(matches!(
+        SetCombined::from_parts("title", Vec::new(), vec![0; 8]),
+        Err(BananaError::NonceWrongLength(8))
+    ));
+}
+
+#[test]
+fn oversized_share_rejected() {
+    let content = vec![0xab; DEFAULT_MAX_CONTENT_LENGTH + 1];
+    let json = crafted_json(
+        2,
+        &format!("8{}", base64::encode([vec![1], content].concat())),
+        NONCE_CRAFTED,
+    );
+    assert_eq!(
+        Share::new(json.clone().into_bytes()).unwrap_err(),
+        BananaError::ShareTooLarge {
+            len: DEFAULT_MAX_CONTENT_LENGTH + 1,
+            max: DEFAULT_MAX_CONTENT_LENGTH,
+        }
+    );
+    let options = ParseOptions {
+        max_content_length: 1 << 20,
+        ..ParseOptions::default()
+    };
+    let share = Share::new_with_options(json.into_bytes(), &options).unwrap();
+    assert_eq!(share.content_len(), DEFAULT_MAX_CONTENT_LENGTH + 1);
+
+    // oversized body is rejected before it is decoded
+    let json = crafted_json(
+        2,
+        &format!("8{}", "!".repeat(4 * DEFAULT_MAX_CONTENT_LENGTH)),
+        NONCE_CRAFTED,
+    );
+    assert!(matches!(
+        Share::new(json.into_bytes()),
+        Err(BananaError::ShareTooLarge { .. })
+    ));
+    let json = format!(
+        r#"{{"t":"{}","r":2,"d":"8{}","n":"{}"}}"#,
+        TITLE_CRAFTED,
+        hex::encode(vec![0xab; DEFAULT_MAX_CONTENT_LENGTH + 2]),
+        NONCE_CRAFTED,
+    );
+    assert_eq!(
+        Share::new(json.into_bytes()).unwrap_err(),
+        BananaError::ShareTooLarge {
+            len: DEFAULT_MAX_CONTENT_LENGTH + 1,
+            max: DEFAULT_MAX_CONTENT_LENGTH,
+        }
+    );
+}
+
+#[test]
+fn required_shares_validated() {
+    let share_requiring = |required_shares: usize| {
+        let json = crafted_json(
+            required_shares,
+            &format!("8{}", base64::encode([1, 0xab])),
+            NONCE_CRAFTED,
+        );
+        Share::new(json.into_bytes())
+    };
+    for required_shares in [0, 1] {
+        assert_eq!(
+            share_requiring(required_shares).unwrap_err(),
+            BananaError::RequiredSharesTooSmall(required_shares)
+        );
+    }
+    assert_eq!(
+        share_requiring(300).unwrap_err(),
+        BananaError::RequiredSharesExceedsField {
+            required: 300,
+            max: 255
+        }
+    );
+    assert!(share_requiring(255).is_ok());
+}
+
+#[test]
+fn duplicate_content_rejected() {
+    let content = [vec![0x00, 0x01], vec![0xab; 16]].concat();
+    let mut share_collection = ShareCollection::new();
+    let _ = share_collection
+        .add_share(share_with_content(1, &content, 2))
+        .unwrap();
+    let share = share_with_content(2, &content, 2);
+    assert!(share_collection.mismatch(&share).unwrap().content_duplicate);
+    assert!(matches!(
+        share_collection.add_share(share),
+        Err(BananaError::ShareContentDuplicate {
+            existing_id: 1,
+            new_id: 2
+        })
+    ));
+    assert_eq!(share_collection.shares_now(), 1);
+
+    // identical content is accepted on request
+    assert_eq!(
+        share_collection
+            .add_share_lenient(share_with_content(2, &content, 2))
+            .unwrap(),
+        AddOutcome::Ready
+    );
+}
+
+#[test]
+fn all_mismatches_reported() {
+    let mut share_collection = ShareCollection::new();
+    let share_b1 = Share::from_hex_payload(SCAN_B1).unwrap();
+    assert_eq!(share_collection.mismatch(&share_b1), None);
+
+    let _ = share_collection
+        .add_share(Share::from_hex_payload(SCAN_A1).unwrap())
+        .unwrap();
+    let mismatch = share_collection.mismatch(&share_b1).unwrap();
+    assert!(!mismatch.is_compatible());
+    assert_eq!(
+        mismatch,
+        ShareMismatch {
+            version: false,
+            title: true,
+            required_shares: false,
+            nonce: true,
+            bits: false,
+            already_in_set: true,
+            content_length: true,
+            content_duplicate: false,
+        }
+    );
+
+    // same share again differs only by being in set already
+    let mismatch = share_collection
+        .mismatch(&Share::from_hex_payload(SCAN_A1).unwrap())
+        .unwrap();
+    assert!(mismatch.already_in_set);
+    assert!(!(mismatch.title || mismatch.nonce || mismatch.content_length));
+    assert!(share_collection
+        .mismatch(&Share::from_hex_payload(SCAN_A2).unwrap())
+        .unwrap()
+        .is_compatible());
+}
+
+#[test]
+fn damaged_share_pinpointed() {
+    let combined = ShareCollection::from_shares(
+        [SCAN_C1, SCAN_C2, SCAN_C3].map(|scan| Share::from_hex_payload(scan).unwrap()),
+    )
+    .unwrap()
+    .take_ready()
+    .unwrap();
+    let share4 = combined.derive_new_share(4).unwrap();
+    let share5 = combined.derive_new_share(5).unwrap();
+    assert!(matches!(
+        combined.consistency_check(),
+        Err(BananaError::ThresholdUnreachable { have: 3, need: 4 })
+    ));
+
+    // share #2 with one content byte damaged
+    let json = String::from_utf8(hex::decode(SCAN_C2).unwrap()).unwrap();
+    let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let mut body = base64::decode(&value["d"].as_str().unwrap()[1..]).unwrap();
+    body[10] ^= 0x40;
+    value["d"] = serde_json::Value::String(format!("8{}", base64::encode(body)));
+    let damaged2 = Share::new(serde_json::to_vec(&value).unwrap()).unwrap();
+
+    let mut set = SetInProgress::init(Share::from_hex_payload(SCAN_C1).unwrap());
+    for share in [
+        damaged2,
+        Share::from_hex_payload(SCAN_C3).unwrap(),
+        share4,
+        share5,
+    ] {
+        set.add_share(share).unwrap();
+    }
+    let report = set.consistency_check().unwrap();
+    assert_eq!(report.subsets_checked, 10);
+    assert!(!report.consistent);
+    assert_eq!(report.outlier(), Some(2));
+
+    // same set without damage is consistent
+    set.remove_share(2).unwrap();
+    set.add_share(Share::from_hex_payload(SCAN_C2).unwrap())
+        .unwrap();
+    let report = set.consistency_check().unwrap();
+    assert!(report.consistent);
+    assert_eq!(report.outlier(), None);
+}
+
+#[test]
+fn chosen_shares_combined() {
+    let mut set = SetInProgress::init(Share::from_hex_payload(SCAN_A1).unwrap());
+    for scan in [SCAN_A2, SCAN_A3] {
+        set.add_share(Share::from_hex_payload(scan).unwrap())
+            .unwrap();
+    }
+    for ids in [[1, 2], [1, 3], [2, 3], [3, 1]] {
+        let combined = set.combine_with(&ids).unwrap();
+        assert_eq!(combined.ids(), &ids);
+        assert_eq!(
+            combined.recover_with_passphrase(PASSPHRASE_A).unwrap(),
+            ALICE_SEEDPHRASE
+        );
+    }
+    assert!(matches!(
+        set.combine_with(&[1]),
+        Err(BananaError::ThresholdUnreachable { have: 1, need: 2 })
+    ));
+    assert!(matches!(
+        set.combine_with(&[1, 1]),
+        Err(BananaError::ShareAlreadyInSet)
+    ));
+    assert!(matches!(
+        set.combine_with(&[1, 4]),
+        Err(BananaError::ShareIdNotInSet(4))
+    ));
+
+    let share_collection = ShareCollection::from_shares(
+        [SCAN_A1, SCAN_A3].map(|scan| Share::from_hex_payload(scan).unwrap()),
+    )
+    .unwrap();
+    let combined = share_collection.combine_subset(&[3, 1]).unwrap();
+    assert_eq!(combined.ids(), &[3, 1]);
+    assert!(matches!(
+        ShareCollection::new().combine_subset(&[1, 2]),
+        Err(BananaError::NoShares)
+    ));
+}
+
+#[test]
+fn error_kinds_mapped() {
+    for (error, kind) in [
+        (BananaError::PayloadNotHex, ErrorKind::InvalidShare),
+        (BananaError::ParseBit('!'), ErrorKind::InvalidShare),
+        (
+            BananaError::RequiredSharesTooSmall(1),
+            ErrorKind::InvalidShare,
+        ),
+        (
+            BananaError::ShareNonceDifferent,
+            ErrorKind::IncompatibleShare,
+        ),
+        (BananaError::ShareAlreadyInSet, ErrorKind::IncompatibleShare),
+        (BananaError::MalformedPadding, ErrorKind::CorruptedSet),
+        (
+            BananaError::ThresholdUnreachable { have: 1, need: 2 },
+            ErrorKind::CorruptedSet,
+        ),
+        (BananaError::NoShares, ErrorKind::CorruptedSet),
+        (BananaError::DecodingFailed, ErrorKind::DecryptionFailed),
+        (BananaError::ScryptFailed, ErrorKind::Internal),
+        (
+            BananaError::ShareIdNotInSet(4),
+            ErrorKind::IncompatibleShare,
+        ),
+        (BananaError::ArchiveEncryptionFailed, ErrorKind::Internal),
+        (
+            BananaError::ShareRejected {
+                index: 1,
+                reason: Box::new(BananaError::ShareTitleDifferent {
+                    set: String::from("a"),
+                    new_share: String::from("b"),
+                }),
+            },
+            ErrorKind::IncompatibleShare,
+        ),
+    ] {
+        assert_eq!(error.kind(), kind, "{}", error);
+    }
+
+    // as received from actual processing
+    assert_eq!(
+        Share::from_hex_payload("not hex").unwrap_err().kind(),
+        ErrorKind::InvalidShare
+    );
+    let mut share_collection = ShareCollection::new();
+    let _ = share_collection
+        .add_share(Share::from_hex_payload(SCAN_A1).unwrap())
+        .unwrap();
+    assert_eq!(
+        share_collection
+            .add_share(Share::from_hex_payload(SCAN_C1).unwrap())
+            .unwrap_err()
+            .kind(),
+        ErrorKind::IncompatibleShare
+    );
+    let _ = share_collection
+        .add_share(Share::from_hex_payload(SCAN_A2).unwrap())
+        .unwrap();
+    assert_eq!(
+        share_collection
+            .ready()
+            .unwrap()
+            .recover_with_passphrase(PASSPHRASE_C)
+            .unwrap_err()
+            .kind(),
+        ErrorKind::DecryptionFailed
+    );
+}
+
+#[test]
+fn error_source_chained() {
+    use core::error::Error;
+
+    let rejected = BananaError::ShareRejected {
+        index: 1,
+        reason: Box::new(BananaError::ShareNonceDifferent),
+    };
+    assert_eq!(
+        format!("{}", rejected.source().unwrap()),
+        format!("{}", BananaError::ShareNonceDifferent)
+    );
+    let incompatible = BananaError::IncompatibleCollections {
+        reason: Box::new(BananaError::ShareAlreadyInSet),
+    };
+    assert_eq!(
+        format!("{}", incompatible.source().unwrap()),
+        format!("{}", BananaError::ShareAlreadyInSet)
+    );
+    assert!(BananaError::DecodingFailed.source().is_none());
+}
+
+#[test]
+fn error_codes_stable() {
+    // snapshot of the current assignments, codes must never change
+    let errors = [
+        (BananaError::BitsOutOfRange(21), 101),
+        (BananaError::BodyNotBase64, 102),
+        (BananaError::EmptyShare, 103),
+        (BananaError::JsonParsing(String::from("eof")), 104),
+        (BananaError::NonceNotBase64, 105),
+        (BananaError::NonceWrongLength(23), 106),
+        (BananaError::NotShareString(0), 107),
+        (BananaError::ParseBit('!'), 108),
+        (BananaError::PayloadNotHex, 109),
+        (
+            BananaError::RequiredSharesExceedsField {
+                required: 300,
+                max: 255,
+            },
+            110,
+        ),
+        (BananaError::RequiredSharesTooSmall(1), 111),
+        (BananaError::ShareIdInvalid(0), 112),
+        (BananaError::ShareTooLarge { len: 2, max: 1 }, 113),
+        (BananaError::ShareTooShort, 114),
+        (BananaError::UndefinedBodyNotHex, 115),
+        (BananaError::VersionNotSupported(2), 116),
+        (BananaError::UriMalformed { reason: "test" }, 117),
+        (BananaError::UriSchemeUnsupported, 118),
+        (
+            BananaError::IncompatibleCollections {
+                reason: Box::new(BananaError::ShareAlreadyInSet),
+            },
+            201,
+        ),
+        (BananaError::ShareAlreadyInSet, 202),
+        (BananaError::ShareBitsDifferent, 203),
+        (BananaError::ShareContentLengthDifferent, 204),
+        (BananaError::ShareIdNotInSet(4), 205),
+        (BananaError::ShareNonceDifferent, 206),
+        (BananaError::ShareRequiredSharesDifferent, 207),
+        (
+            BananaError::ShareTitleDifferent {
+                set: String::from("a"),
+                new_share: String::from("b"),
+            },
+            208,
+        ),
+        (BananaError::ShareVersionDifferent, 209),
+        (BananaError::CombinedDataMalformed(1), 301),
+        (BananaError::DecodedSecretNotString, 302),
+        (BananaError::DecompressionFailed, 303),
+        (BananaError::LogOutOfRange(0), 304),
+        (BananaError::MalformedPadding, 305),
+        (BananaError::SecretHashMismatch, 306),
+        (
+            BananaError::ShareContentDuplicate {
+                existing_id: 1,
+                new_id: 2,
+            },
+            307,
+        ),
+        (BananaError::ThresholdUnreachable { have: 1, need: 2 }, 308),
+        (BananaError::VerificationMismatch, 309),
+        (BananaError::InsufficientShares { have: 1, need: 2 }, 310),
+        (BananaError::NoShares, 311),
+        (BananaError::DecodingFailed, 401),
+        (BananaError::ArchiveReadFailed, 501),
+        (BananaError::Argon2Failed, 502),
+        (BananaError::FieldInvariantViolated { context: "test" }, 503),
+        (BananaError::PolynomialMissing(21), 504),
+        (BananaError::PolynomialNotPrimitive(0), 505),
+        (BananaError::ScryptFailed, 506),
+        (
+            BananaError::SplitParametersInvalid {
+                total_shares: 1,
+                required_shares: 2,
+            },
+            507,
+        ),
+        (BananaError::VectorMismatch(0), 508),
+        (BananaError::DerivedShareUnrepresentable(3), 509),
+        (BananaError::ArchiveEncryptionFailed, 510),
+    ];
+    assert_eq!(errors.len(), ERROR_CODES.len());
+    for (error, code) in errors.iter() {
+        assert_eq!(error.code(), *code, "{:?}", error);
+        let (_, name) = ERROR_CODES
+            .iter()
+            .find(|(listed, _)| listed == code)
+            .unwrap();
+        assert!(format!("{:?}", error).starts_with(name), "{:?}", error);
+    }
+
+    // no code is used twice
+    for (i, (code, name)) in ERROR_CODES.iter().enumerate() {
+        assert!(ERROR_CODES[i + 1..]
+            .iter()
+            .all(|(other_code, other_name)| other_code != code && other_name != name));
+    }
+
+    let rejected = BananaError::ShareRejected {
+        index: 1,
+        reason: Box::new(BananaError::ShareNonceDifferent),
+    };
+    assert_eq!(rejected.code(), SHARE_REJECTED_CODE_OFFSET + 206);
+}
+
+#[cfg(feature = "defmt")]
+#[test]
+fn defmt_formatting_available() {
+    fn formattable<T: defmt::Format>() {}
+    formattable::<BananaError>();
+    formattable::<Version>();
+    formattable::<Progress>();
+}
+
+/// Subscriber recording all events and spans, as `name: field=value` lines.
+#[cfg(all(feature = "tracing", feature = "std"))]
+struct CapturingSubscriber {
+    records: std::sync::Arc<std::sync::Mutex<Vec<String>>>,
+    next_id: core::sync::atomic::AtomicU64,
+}
+
+#[cfg(all(feature = "tracing", feature = "std"))]
+struct RecordVisitor(String);
+
+#[cfg(all(feature = "tracing", feature = "std"))]
+impl tracing::field::Visit for RecordVisitor {
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn core::fmt::Debug) {
+        self.0 += &format!(" {}={:?}", field.name(), value);
+    }
+}
+
+#[cfg(all(feature = "tracing", feature = "std"))]
+impl tracing::Subscriber for CapturingSubscriber {
+    fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+        true
+    }
+    fn new_span(&self, span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+        let mut visitor = RecordVisitor(format!("span {}:", span.metadata().name()));
+        span.record(&mut visitor);
+        self.records.lock().unwrap().push(visitor.0);
+        tracing::span::Id::from_u64(
+            self.next_id
+                .fetch_add(1, core::sync::atomic::Ordering::Relaxed),
+        )
+    }
+    fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+    fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+    fn event(&self, event: &tracing::Event<'_>) {
+        let mut visitor = RecordVisitor(String::from("event:"));
+        event.record(&mut visitor);
+        self.records.lock().unwrap().push(visitor.0);
+    }
+    fn enter(&self, _: &tracing::span::Id) {}
+    fn exit(&self, _: &tracing::span::Id) {}
+}
+
+#[cfg(all(feature = "tracing", feature = "std"))]
+#[test]
+fn recovery_traced() {
+    let records = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let subscriber = CapturingSubscriber {
+        records: records.clone(),
+        next_id: core::sync::atomic::AtomicU64::new(1),
+    };
+    tracing::subscriber::with_default(subscriber, || {
+        let mut share_collection = ShareCollection::new();
+        for scan in [SCAN_A1, SCAN_A2] {
+            let _ = share_collection
+                .add_share(Share::from_hex_payload(scan).unwrap())
+                .unwrap();
+        }
+        assert!(Share::new(b"not json".to_vec()).is_err());
+        let combined = share_collection.ready().unwrap();
+        assert!(combined.recover_with_passphrase(PASSPHRASE_C).is_err());
+        assert_eq!(
+            combined.recover_with_passphrase(PASSPHRASE_A).unwrap(),
+            ALICE_SEEDPHRASE
+        );
+    });
+    let records = records.lock().unwrap();
+    for expected in [
+        r#"event: message=share parsed version=V1 bits=8 id=1 content_length=96"#,
+        r#"event: message=share parsed version=V1 bits=8 id=2 content_length=96"#,
+        "span combine: shares=2 bits=8",
+        "event: message=collection ready shares=2 required_shares=2",
+        "event: message=share rejected error_code=104",
+        "span derive_key: log_n=15",
+        "event: message=decryption attempted success=false",
+        "event: message=decryption attempted success=true",
+    ] {
+        assert!(
+            records.iter().any(|record| record == expected),
+            "{} not in {:?}",
+            expected,
+            records
+        );
+    }
+
+    // nothing sensitive is ever recorded
+    let nonce = "o9DbpBi9r7UWJHOriuDArR4Vrc0VOo3l";
+    for record in records.iter() {
+        for sensitive in [nonce, PASSPHRASE_A, PASSPHRASE_C, "bottom", "title"] {
+            assert!(!record.contains(sensitive), "{}", record);
+        }
+    }
+}
+
+#[test]
+fn version_described() {
+    let share = Share::new(hex::decode(SCAN_C1).unwrap()).unwrap();
+    assert_eq!(share.version().as_u8(), Some(1));
+    assert_eq!(format!("{}", share.version()), "v1");
+    assert_eq!(Version::Undefined.as_u8(), None);
+    assert_eq!(format!("{}", Version::Undefined), "legacy/undefined");
+}
+
+#[test]
+fn versions_supported() {
+    assert!(Version::is_supported(1));
+    assert!(!Version::is_supported(0));
+    assert!(!Version::is_supported(2));
+    for spec in crate::shares::VERSION_SPECS.iter() {
+        assert_eq!(spec.version.spec().number, spec.number);
+        assert_eq!(spec.version.as_u8(), spec.number);
+        if let Some(number) = spec.number {
+            assert!(Version::is_supported(number));
+        }
+    }
+}
+
+#[test]
+fn quoted_version_accepted() {
+    let share_with_version = |v: &str| {
+        Share::from_json_str(&format!(
+            r#"{{"v":{},"t":"{}","r":2,"d":"8{}","n":"{}"}}"#,
+            v,
+            TITLE_CRAFTED,
+            base64::encode(b"\x01content"),
+            NONCE_CRAFTED,
+        ))
+    };
+    assert_eq!(share_with_version("1").unwrap().version(), Version::V1);
+    assert_eq!(share_with_version(r#""1""#).unwrap().version(), Version::V1);
+    assert_eq!(
+        share_with_version(r#""2""#).unwrap_err(),
+        BananaError::VersionNotSupported(2)
+    );
+    assert!(matches!(
+        share_with_version(r#""one""#),
+        Err(BananaError::JsonParsing(_))
+    ));
+    assert!(matches!(
+        share_with_version("256"),
+        Err(BananaError::JsonParsing(_))
+    ));
+
+    // quoted version is written back as a number
+    let share = share_with_version(r#""1""#).unwrap();
+    assert_eq!(
+        Share::new(share.to_qr_payload()).unwrap().version(),
+        Version::V1
+    );
+    assert!(String::from_utf8(share.to_qr_payload())
+        .unwrap()
+        .starts_with(r#"{"v":1,"#));
+}
+
+#[test]
+fn strict_parsing() {
+    let json = crafted_json(
+        2,
+        &format!("8{}", base64::encode(b"\x01content")),
+        NONCE_CRAFTED,
+    );
+    assert!(Share::new_strict(json.clone().into_bytes()).is_ok());
+
+    // extra field
+    let extra = json.replacen('{', r#"{"x":0,"#, 1);
+    assert!(Share::new(extra.clone().into_bytes()).is_ok());
+    assert!(matches!(
+        Share::new_strict(extra.into_bytes()),
+        Err(BananaError::JsonParsing(_))
+    ));
+
+    // data after the closing brace, scanner noise included
+    for trailing in ["garbage", "\n", "\0"] {
+        let padded = format!("{}{}", json, trailing);
+        assert!(matches!(
+            Share::new_strict(padded.into_bytes()),
+            Err(BananaError::JsonParsing(_))
+        ));
+    }
+    assert!(Share::new(format!("{}\n\0", json).into_bytes()).is_ok());
+
+    // duplicate field is rejected in any mode
+    let duplicate = json.replacen('{', r#"{"r":2,"#, 1);
+    assert!(matches!(
+        Share::new(duplicate.clone().into_bytes()),
+        Err(BananaError::JsonParsing(_))
+    ));
+    assert!(matches!(
+        Share::new_strict(duplicate.into_bytes()),
+        Err(BananaError::JsonParsing(_))
+    ));
+}
+
+#[cfg(feature = "arbitrary")]
+#[test]
+fn random_payloads_processed_without_panic() {
+    use rand_core::RngCore;
+
+    let mut rng = TestRng(0x2545_f491_4f6c_dd1d);
+    let mut parsed = 0;
+    let mut combined = 0;
+    for round in 0..4000 {
+        let mut raw = vec![0; 16 + round % 512];
+        rng.fill_bytes(&mut raw);
+        let mut u = arbitrary::Unstructured::new(&raw);
+        let set: crate::FuzzSet = match arbitrary::Arbitrary::arbitrary(&mut u) {
+            Ok(a) => a,
+            Err(_) => continue,
+        };
+
+        // same as in `share_set` fuzz target
+        let mut share_collection = ShareCollection::new();
+        for payload in set.payloads {
+            if let Ok(share) = Share::new(payload) {
+                parsed += 1;
+                match share_collection.add_share_lenient(share) {
+                    Err(e) if e.kind() == ErrorKind::CorruptedSet => combined += 1,
+                    Ok(AddOutcome::Ready) => combined += 1,
+                    _ => {}
+                }
+            }
+        }
+        match &share_collection {
+            ShareCollection::Empty => {}
+            ShareCollection::InProgress(in_progress) => {
+                let _ = in_progress.consistency_check();
+                let _ = in_progress.derive_new_share(set.new_id);
+            }
+            ShareCollection::Ready(ready) => {
+                let _ = ready.consistency_check();
+                let _ = ready.derive_new_share(set.new_id);
+            }
+        }
+        let _ = Share::new_strict(raw);
+    }
+    assert!(
+        parsed > 0,
+        "some of the generated payloads must be valid shares"
+    );
+    assert!(
+        combined > 0,
+        "some of the generated sets must get to combining"
+    );
+}
+
+#[test]
+fn random_blobs_parsed_without_panic() {
+    use rand_core::RngCore;
+
+    let mut rng = TestRng(0x9e37_79b9_7f4a_7c15);
+    let valid = hex::decode(SCAN_A1).unwrap();
+    let mut combined = 0;
+    for round in 0..4000 {
+        let mut blob = vec![0; round % 300];
+        rng.fill_bytes(&mut blob);
+        let _ = Share::new(blob.clone());
+        let _ = Share::new_strict(blob);
+
+        // valid share json with garbage: bytes replaced, cut, or inserted
+        let mut payload = valid.clone();
+        for _ in 0..1 + rng.next_u32() % 3 {
+            let position = rng.next_u32() as usize % payload.len();
+            match rng.next_u32() % 3 {
+                0 => payload[position] = rng.next_u32() as u8,
+                1 => payload.truncate(position.max(1)),
+                _ => payload.insert(position, rng.next_u32() as u8),
+            }
+        }
+        let _ = Share::new_strict(payload.clone());
+        if let Ok(share) = Share::new(payload) {
+            // combined with an intact share of the same set, if still fits
+            let mut share_collection = ShareCollection::new();
+            let _ = share_collection
+                .add_share_lenient(Share::new(hex::decode(SCAN_A2).unwrap()).unwrap())
+                .unwrap();
+            if share_collection.add_share_lenient(share).is_ok() {
+                combined += 1;
+            }
+        }
+    }
+    assert!(combined > 0, "some of the damaged shares must get combined");
+}
+
+#[test]
+fn share_from_uri() {
+    // share body has `+`, that must not be decoded as space
+    let payload = hex::decode(SCAN_A2).unwrap();
+    let share = Share::new(payload.clone()).unwrap();
+
+    // percent-encoding with only the unreserved chars left as is
+    let percent_encoded: String = payload
+        .iter()
+        .map(|byte| {
+            if byte.is_ascii_alphanumeric() || b"-._~".contains(byte) {
+                format!("{}", *byte as char)
+            } else {
+                format!("%{:02X}", byte)
+            }
+        })
+        .collect();
+    assert!(percent_encoded.contains("%2B"));
+    for uri in [
+        format!("banana://share?d={}", percent_encoded),
+        format!("BANANA://share?v=1&d={}#scan", percent_encoded),
+        format!("data:application/json,{}", percent_encoded),
+        format!("data:application/json;base64,{}", base64::encode(&payload)),
+        format!(
+            " data:Application/JSON;charset=utf-8;base64,{}\n",
+            base64::encode(&payload).replace('=', "%3D")
+        ),
+    ] {
+        assert_eq!(Share::from_uri(&uri).unwrap(), share, "{}", uri);
+    }
+
+    assert_eq!(
+        Share::from_uri("https://example.com/?d=%7B%7D").unwrap_err(),
+        BananaError::UriSchemeUnsupported
+    );
+    for (uri, reason) in [
+        ("banana://share?t=title", "no share data parameter"),
+        (
+            "banana://share?d=%7B&d=%7B",
+            "repeated share data parameter",
+        ),
+        ("banana://other?d=%7B%7D", "banana link is not a share link"),
+        ("banana://share?d=%7", "invalid percent-encoding"),
+        ("banana://share?d=%zz", "invalid percent-encoding"),
+        ("data:application/json;base64", "no data separator"),
+        (
+            "data:text/plain,%7B%7D",
+            "media type is not application/json",
+        ),
+        ("data:;base64,e30=", "media type is not application/json"),
+        (
+            "data:application/json;base64,e30*",
+            "data is not in base64 format",
+        ),
+    ] {
+        assert_eq!(
+            Share::from_uri(uri).unwrap_err(),
+            BananaError::UriMalformed { reason },
+            "{}",
+            uri
+        );
+    }
+
+    // extracted json is processed as usual
+    assert!(matches!(
+        Share::from_uri("data:application/json,%7B%7D"),
+        Err(BananaError::JsonParsing(_))
+    ));
+}
+
+#[cfg(feature = "ffi")]
+#[test]
+fn ffi_recovery() {
+    use crate::ffi::*;
+    use core::ffi::{c_char, CStr};
+
+    unsafe {
+        let collection = banana_collection_new();
+        assert_eq!(banana_collection_status(collection), BANANA_STATUS_EMPTY);
+        let mut buf = [0x55 as c_char; 64];
+        assert_eq!(
+            banana_collection_title(
+                collection,
+                buf.as_mut_ptr(),
+                buf.len(),
+                core::ptr::null_mut()
+            ),
+            BANANA_NO_TITLE
+        );
+        assert!(banana_collection_combined(collection).is_null());
+
+        // invalid share, error code as in `BananaError::code`
+        let garbage = b"not json";
+        assert_eq!(
+            banana_collection_add_share(collection, garbage.as_ptr(), garbage.len()),
+            i32::from(Share::new(garbage.to_vec()).unwrap_err().code())
+        );
+
+        let scan = hex::decode(SCAN_A1).unwrap();
+        assert_eq!(
+            banana_collection_add_share(collection, scan.as_ptr(), scan.len()),
+            BANANA_OK
+        );
+        assert_eq!(
+            banana_collection_status(collection),
+            BANANA_STATUS_IN_PROGRESS
+        );
+
+        // title buffer must fit title and nul, untouched otherwise,
+        // needed capacity is reported
+        let title = "Alice tries BananaSplit again";
+        let mut needed = 0;
+        assert_eq!(
+            banana_collection_title(collection, core::ptr::null_mut(), 0, &mut needed),
+            BANANA_BUFFER_TOO_SMALL
+        );
+        assert_eq!(needed, title.len() + 1);
+        assert_eq!(
+            banana_collection_title(collection, buf.as_mut_ptr(), title.len(), &mut needed),
+            BANANA_BUFFER_TOO_SMALL
+        );
+        assert!(buf.iter().all(|a| *a == 0x55));
+        assert_eq!(
+            banana_collection_title(collection, buf.as_mut_ptr(), needed, core::ptr::null_mut()),
+            BANANA_OK
+        );
+        assert_eq!(CStr::from_ptr(buf.as_ptr()).to_str().unwrap(), title);
+        assert_eq!(buf[title.len() + 1], 0x55);
+
+        let scan = hex::decode(SCAN_A2).unwrap();
+        assert_eq!(
+            banana_collection_add_share(collection, scan.as_ptr(), scan.len()),
+            BANANA_OK
+        );
+        assert_eq!(banana_collection_status(collection), BANANA_STATUS_READY);
+        let combined = banana_collection_combined(collection);
+        assert!(!combined.is_null());
+
+        let passphrase = alloc::ffi::CString::new(PASSPHRASE_A).unwrap();
+        let passphrase = passphrase.as_ptr();
+        let mut secret = [0 as c_char; 128];
+        let mut needed = 0;
+        assert_eq!(
+            banana_combined_recover(combined, passphrase, secret.as_mut_ptr(), 8, &mut needed),
+            BANANA_BUFFER_TOO_SMALL
+        );
+        assert_eq!(needed, ALICE_SEEDPHRASE.len() + 1);
+        assert_eq!(
+            banana_combined_recover(
+                combined,
+                passphrase,
+                secret.as_mut_ptr(),
+                secret.len(),
+                core::ptr::null_mut()
+            ),
+            BANANA_OK
+        );
+        assert_eq!(
+            CStr::from_ptr(secret.as_ptr()).to_str().unwrap(),
+            ALICE_SEEDPHRASE
+        );
+        assert_eq!(
+            banana_combined_recover(
+                combined,
+                c"wrong".as_ptr(),
+                secret.as_mut_ptr(),
+                secret.len(),
+                core::ptr::null_mut()
+            ),
+            i32::from(BananaError::DecodingFailed.code())
+        );
+
+        // secret with nul inside is not written at all
+        let with_nul = combined_from_secret("nul", b"before\0after", PASSPHRASE_A);
+        let mut secret_with_nul = [0x55 as c_char; 32];
+        assert_eq!(
+            banana_combined_recover(
+                &with_nul,
+                passphrase,
+                secret_with_nul.as_mut_ptr(),
+                secret_with_nul.len(),
+                &mut needed
+            ),
+            BANANA_INTERIOR_NUL
+        );
+        assert!(secret_with_nul.iter().all(|a| *a == 0x55));
+        assert!(banana_combined_recover_string(&with_nul, passphrase).is_null());
+
+        let secret = banana_combined_recover_string(combined, passphrase);
+        assert_eq!(CStr::from_ptr(secret).to_str().unwrap(), ALICE_SEEDPHRASE);
+        banana_string_free(secret);
+        assert!(banana_combined_recover_string(combined, c"wrong".as_ptr()).is_null());
+
+        // null pointers are rejected
+        assert_eq!(
+            banana_collection_status(core::ptr::null()),
+            BANANA_NULL_POINTER
+        );
+        assert_eq!(
+            banana_collection_add_share(collection, core::ptr::null(), 0),
+            BANANA_NULL_POINTER
+        );
+        assert_eq!(
+            banana_combined_recover(
+                core::ptr::null(),
+                passphrase,
+                core::ptr::null_mut(),
+                0,
+                core::ptr::null_mut()
+            ),
+            BANANA_NULL_POINTER
+        );
+        banana_string_free(core::ptr::null_mut());
+
+        banana_collection_free(collection);
+        banana_collection_free(core::ptr::null_mut());
+    }
+}
+
+#[cfg(feature = "uniffi")]
+#[test]
+fn mobile_recovery() {
+    use crate::{MobileError, MobileShare, MobileShareCollection};
+
+    let collection = MobileShareCollection::new();
+    assert_eq!(collection.shares_now(), 0);
+    assert_eq!(collection.shares_required(), None);
+    assert!(collection.combined().is_none());
+
+    assert!(matches!(
+        MobileShare::new(b"not json".to_vec()),
+        Err(MobileError::InvalidShare { code: 104, .. })
+    ));
+
+    let share = MobileShare::new(hex::decode(SCAN_A1).unwrap()).unwrap();
+    assert_eq!(share.id(), 1);
+    assert_eq!(share.required_shares(), 2);
+    collection.add_share(share.clone()).unwrap();
+    match collection.add_share(share) {
+        Err(MobileError::IncompatibleShare { code, text }) => {
+            assert_eq!(code, BananaError::ShareAlreadyInSet.code());
+            assert_eq!(text, format!("{}", BananaError::ShareAlreadyInSet));
+        }
+        other => panic!("Expected incompatible share error, got {:?}", other),
+    }
+    assert_eq!(collection.shares_required(), Some(2));
+    assert_eq!(
+        collection.title().as_deref(),
+        Some("Alice tries BananaSplit again")
+    );
+    assert!(!collection.is_ready());
+
+    collection
+        .add_share(MobileShare::new(hex::decode(SCAN_A2).unwrap()).unwrap())
+        .unwrap();
+    assert_eq!(collection.shares_now(), 2);
+    let combined = collection.combined().unwrap();
+    assert!(matches!(
+        combined.recover_with_passphrase(String::from("wrong")),
+        Err(MobileError::DecryptionFailed { .. })
+    ));
+    assert_eq!(
+        combined
+            .recover_with_passphrase(String::from(PASSPHRASE_A))
+            .unwrap(),
+        ALICE_SEEDPHRASE
+    );
+}
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+mod wasm {
+    use wasm_bindgen::{JsCast, JsValue};
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::{ALICE_SEEDPHRASE, PASSPHRASE_A, SCAN_A1, SCAN_A3};
+    use crate::{BananaError, JsShareCollection};
+
+    /// Code of the js error.
+    fn code(error: JsValue) -> u16 {
+        js_sys::Reflect::get(&error, &JsValue::from_str("code"))
+            .unwrap()
+            .as_f64()
+            .unwrap() as u16
+    }
+
+    #[wasm_bindgen_test]
+    fn alice_recovered() {
+        let mut collection = JsShareCollection::new();
+        assert_eq!(collection.shares_now(), 0);
+        assert_eq!(collection.shares_required(), None);
+        assert_eq!(collection.title(), None);
+        assert_eq!(
+            code(collection.recover(PASSPHRASE_A).unwrap_err()),
+            BananaError::NoShares.code()
+        );
+
+        collection
+            .add_share(&hex::decode(SCAN_A1).unwrap())
+            .unwrap();
+        let error = collection
+            .add_share(&hex::decode(SCAN_A1).unwrap())
+            .unwrap_err();
+        assert!(error.is_instance_of::<js_sys::Error>());
+        assert_eq!(code(error), BananaError::ShareAlreadyInSet.code());
+        assert_eq!(collection.shares_now(), 1);
+        assert_eq!(collection.shares_required(), Some(2));
+        assert_eq!(
+            collection.title().as_deref(),
+            Some("Alice tries BananaSplit again")
+        );
+        assert!(!collection.is_ready());
+
+        collection
+            .add_share(&hex::decode(SCAN_A3).unwrap())
+            .unwrap();
+        assert!(collection.is_ready());
+        assert_eq!(
+            code(collection.recover("wrong").unwrap_err()),
+            BananaError::DecodingFailed.code()
+        );
+        assert_eq!(collection.recover(PASSPHRASE_A).unwrap(), ALICE_SEEDPHRASE);
+    }
+}
+
+#[test]
+fn set_capacity_reserved() {
+    let mut in_progress = SetInProgress::init(share_with_content(1, b"content", 3));
+    let (ids, contents) = in_progress.capacity();
+    assert!(ids >= 3 && contents >= 3);
+
+    // shares up to the required number are added without reallocation
+    for (id, content) in [(2, b"Content"), (3, b"cOntent")] {
+        in_progress
+            .add_share(share_with_content(id, content, 3))
+            .unwrap();
+    }
+    assert_eq!(in_progress.capacity(), (ids, contents));
+
+    // largest number of required shares allowed for the field
+    let in_progress = SetInProgress::init(share_with_content(1, b"content", 255));
+    let (ids, contents) = in_progress.capacity();
+    assert!(ids >= 255 && contents >= 255);
+}
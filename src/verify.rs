@@ -0,0 +1,38 @@
+//! Recovery verification against known test vectors.
+use alloc::vec::Vec;
+
+use crate::error::BananaError;
+use crate::shares::{Share, ShareCollection};
+
+/// Verify that the secrets are recovered correctly for all known test vectors.
+///
+/// Each vector is a tuple of share QR payloads, passphrase, and the expected
+/// secret. For each vector, the shares are collected and combined, and the
+/// secret is recovered with the passphrase.
+///
+/// Intended for integrators checking the recovery on their hardware, for
+/// example at startup, with the vectors bundled for the deployment.
+///
+/// The first failed vector is reported: its errors are passed through as is,
+/// if not enough shares are provided,
+/// [`BananaError::ThresholdUnreachable`] is returned, and if the recovered
+/// secret is different from the expected one (or there are no shares at all),
+/// [`BananaError::VectorMismatch`] with the vector index is returned.
+pub fn verify_vectors(vectors: &[(Vec<Vec<u8>>, &str, &str)]) -> Result<(), BananaError> {
+    for (index, (payloads, passphrase, expected_secret)) in vectors.iter().enumerate() {
+        let mut share_collection = ShareCollection::new();
+        for payload in payloads.iter() {
+            let _ = share_collection.add_share(Share::new(payload.to_vec())?)?;
+        }
+        match share_collection {
+            ShareCollection::Empty => return Err(BananaError::VectorMismatch(index)),
+            ShareCollection::InProgress(in_progress) => in_progress.check_threshold()?,
+            ShareCollection::Ready(combined) => {
+                if combined.recover_with_passphrase(passphrase)? != *expected_secret {
+                    return Err(BananaError::VectorMismatch(index));
+                }
+            }
+        }
+    }
+    Ok(())
+}
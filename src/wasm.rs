@@ -0,0 +1,92 @@
+//! Bindings for recovery in browser, with `wasm-bindgen`.
+//!
+//! Errors are thrown as js `Error` with `message` from the error text and
+//! numeric `code` from [`BananaError::code`].
+//!
+//! Recovery of `V1` shares runs scrypt with `N = 2^15`, `r = 8` and needs
+//! about 32 MiB of memory, well within the wasm memory limits.
+//!
+//! Bindings are tested with `wasm-bindgen-test-runner` set as the cargo
+//! runner for `wasm32-unknown-unknown`:
+//!
+//! ```text
+//! cargo test --release --target wasm32-unknown-unknown --features wasm --lib
+//! ```
+use alloc::string::{String, ToString};
+use js_sys::{Error, Reflect};
+use wasm_bindgen::prelude::{wasm_bindgen, JsValue};
+
+use crate::error::BananaError;
+use crate::shares::{Share, ShareCollection};
+
+/// Shares collector, for js.
+#[wasm_bindgen]
+#[derive(Debug, Default)]
+pub struct JsShareCollection {
+    collection: ShareCollection,
+}
+
+#[wasm_bindgen]
+impl JsShareCollection {
+    /// Initiate new share collecting.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add share from QR code payload bytes.
+    #[wasm_bindgen(js_name = addShare)]
+    pub fn add_share(&mut self, payload: &[u8]) -> Result<(), JsValue> {
+        let share = Share::new(payload.to_vec()).map_err(js_error)?;
+        let _ = self.collection.add_share(share).map_err(js_error)?;
+        Ok(())
+    }
+
+    /// Number of shares collected so far.
+    #[wasm_bindgen(js_name = sharesNow)]
+    pub fn shares_now(&self) -> usize {
+        self.collection.shares_now()
+    }
+
+    /// Number of shares required, `undefined` if no shares are collected.
+    #[wasm_bindgen(js_name = sharesRequired)]
+    pub fn shares_required(&self) -> Option<usize> {
+        self.collection.shares_required()
+    }
+
+    /// Set title, `undefined` if no shares are collected.
+    pub fn title(&self) -> Option<String> {
+        self.collection.title().map(ToString::to_string)
+    }
+
+    /// Shares are combined, and secret could be recovered.
+    #[wasm_bindgen(js_name = isReady)]
+    pub fn is_ready(&self) -> bool {
+        self.collection.is_ready()
+    }
+
+    /// Recover the secret with user-provided passphrase.
+    ///
+    /// Secret is copied into js string, and could not be zeroized there.
+    pub fn recover(&self, passphrase: &str) -> Result<String, JsValue> {
+        let combined = match &self.collection {
+            ShareCollection::Empty => Err(BananaError::NoShares),
+            ShareCollection::InProgress(in_progress) => Err(BananaError::InsufficientShares {
+                have: in_progress.shares_now(),
+                need: in_progress.shares_required(),
+            }),
+            ShareCollection::Ready(combined) => Ok(combined),
+        }
+        .map_err(js_error)?;
+        combined
+            .recover_with_passphrase(passphrase)
+            .map_err(js_error)
+    }
+}
+
+/// Js `Error` with error text and error code.
+fn js_error(e: BananaError) -> JsValue {
+    let error = Error::new(&e.to_string());
+    let _ = Reflect::set(&error, &JsValue::from_str("code"), &JsValue::from(e.code()));
+    error.into()
+}